@@ -0,0 +1,30 @@
+//! Compares the single time-ordered sweep of `aggregate` with the naive scan
+//! it replaced, on generated coverage series.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use ityfuzz_analyzer::plot::{aggregate, generate_stats, naive_aggregate};
+use ityfuzz_analyzer::types::AggregateMode;
+use std::hint::black_box;
+
+fn bench_aggregate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("aggregate");
+    group.sample_size(10);
+    for (contracts, samples) in [(10, 50), (20, 100), (40, 200)] {
+        let all_contract_stats = generate_stats(contracts, samples);
+        let size = format!("{}x{}", contracts, samples);
+        group.bench_with_input(
+            BenchmarkId::new("sweep", &size),
+            &all_contract_stats,
+            |b, stats| b.iter(|| aggregate(black_box(stats), AggregateMode::Sum)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("naive", &size),
+            &all_contract_stats,
+            |b, stats| b.iter(|| naive_aggregate(black_box(stats), AggregateMode::Sum)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_aggregate);
+criterion_main!(benches);
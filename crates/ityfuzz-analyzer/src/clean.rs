@@ -0,0 +1,91 @@
+use crate::types::CleanArgs;
+use eyre::{Result, WrapErr, eyre};
+use glob::glob;
+use std::fs::{self};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Files generated by the `run` and `plot` commands inside the output directory
+const GENERATED_FILE_PATTERNS: &[&str] = &[
+    "*.instructions.stats.csv",
+    "*.meta.json",
+    "*.bugs.csv",
+    "*_overall_*_plot.png",
+    "*_overall_instructions_stats*.csv",
+    "*_overall_bugs_stats.csv",
+    "*_aggregate_debug.csv",
+    "*_small_multiples_*.png",
+    "*.tmp.png",
+    "*.tmp.csv",
+    "run_manifest.json",
+    "report.html",
+    "warnings.json",
+];
+
+pub fn handle_clean_command(args: CleanArgs) -> Result<()> {
+    if !args.output_dir.is_dir() {
+        return Err(eyre!(
+            "Output directory {} does not exist or is not a directory.",
+            args.output_dir.display()
+        ));
+    }
+
+    let generated_files = find_generated_files(&args.output_dir)?;
+    if generated_files.is_empty() {
+        return Err(eyre!(
+            "{} does not look like an analysis output directory (no generated files found), refusing to clean it.",
+            args.output_dir.display()
+        ));
+    }
+
+    let work_dirs_root = args.workdir_root.as_path();
+    let remove_work_dirs = args.include_workdirs && work_dirs_root.is_dir();
+
+    if args.dry_run {
+        println!("Would remove {} files:", generated_files.len());
+        for path in &generated_files {
+            println!("  {}", path.display());
+        }
+        if remove_work_dirs {
+            println!("Would remove directory {}", work_dirs_root.display());
+        }
+        return Ok(());
+    }
+
+    for path in &generated_files {
+        info!("Removing {}", path.display());
+        fs::remove_file(path)
+            .wrap_err_with(|| format!("Failed to remove file: {}", path.display()))?;
+    }
+    if remove_work_dirs {
+        info!("Removing {}", work_dirs_root.display());
+        fs::remove_dir_all(work_dirs_root).wrap_err_with(|| {
+            format!("Failed to remove directory: {}", work_dirs_root.display())
+        })?;
+    }
+
+    info!(
+        "Clean command complete. Removed {} files from '{}'.",
+        generated_files.len(),
+        args.output_dir.display()
+    );
+    Ok(())
+}
+
+fn find_generated_files(output_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for pattern in GENERATED_FILE_PATTERNS {
+        let glob_pattern = output_dir.join(pattern).to_string_lossy().into_owned();
+        let glob_results = glob(&glob_pattern)
+            .wrap_err_with(|| format!("Invalid glob pattern: '{}'", glob_pattern))?;
+        for entry_result in glob_results {
+            let path = entry_result.wrap_err("Error processing a path from glob pattern")?;
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
@@ -0,0 +1,99 @@
+use crate::plot::load_contract_stats;
+use crate::types::{DiffArgs, StatsEntry};
+use eyre::{Result, eyre};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use tracing::info;
+
+/// Instructions covered by the last sample of a contract's run
+fn final_coverage(entries: &[StatsEntry]) -> u64 {
+    entries
+        .iter()
+        .max_by_key(|e| e.time_taken_millis)
+        .map_or(0, |e| e.instructions_covered)
+}
+
+/// Relative change from `baseline` to `current` in percent
+fn percent_change(baseline: u64, current: u64) -> f64 {
+    if baseline == 0 {
+        if current == 0 { 0.0 } else { 100.0 }
+    } else {
+        (current as f64 - baseline as f64) / baseline as f64 * 100.0
+    }
+}
+
+fn load_final_coverages(dir: &Path) -> Result<HashMap<String, u64>> {
+    if !dir.is_dir() {
+        return Err(eyre!(
+            "Output directory {} does not exist or is not a directory.",
+            dir.display()
+        ));
+    }
+    Ok(load_contract_stats(dir, None)?
+        .into_iter()
+        .map(|(contract_id, entries)| (contract_id, final_coverage(&entries)))
+        .collect())
+}
+
+/// Compares the final coverage of each contract between two output
+/// directories. Contracts missing from the current run count as a full
+/// regression.
+pub fn handle_diff_command(args: DiffArgs) -> Result<()> {
+    let baseline = load_final_coverages(&args.baseline)?;
+    let current = load_final_coverages(&args.current)?;
+
+    let contract_ids: BTreeSet<&String> = baseline.keys().chain(current.keys()).collect();
+    let mut regressed: Vec<&str> = Vec::new();
+
+    println!(
+        "{:<40} {:>12} {:>12} {:>10} {:>9}",
+        "contract", "baseline", "current", "delta", "delta(%)"
+    );
+    for contract_id in contract_ids {
+        let baseline_cov = baseline.get(contract_id);
+        let current_cov = current.get(contract_id);
+        let fmt = |cov: Option<&u64>| cov.map_or_else(|| "-".to_string(), |c| c.to_string());
+
+        let (delta, change) = match (baseline_cov, current_cov) {
+            (Some(&b), Some(&c)) => (c as i64 - b as i64, percent_change(b, c)),
+            (Some(&b), None) => (-(b as i64), -100.0),
+            (None, Some(&c)) => (c as i64, 100.0),
+            (None, None) => unreachable!("contract id comes from one of the maps"),
+        };
+        if baseline_cov.is_some() && -change > args.threshold {
+            regressed.push(contract_id);
+        }
+
+        println!(
+            "{:<40} {:>12} {:>12} {:>10} {:>8.2}%",
+            contract_id,
+            fmt(baseline_cov),
+            fmt(current_cov),
+            delta,
+            change
+        );
+    }
+
+    let baseline_total: u64 = baseline.values().sum();
+    let current_total: u64 = current.values().sum();
+    println!(
+        "{:<40} {:>12} {:>12} {:>10} {:>8.2}%",
+        "TOTAL",
+        baseline_total,
+        current_total,
+        current_total as i64 - baseline_total as i64,
+        percent_change(baseline_total, current_total)
+    );
+
+    if !regressed.is_empty() {
+        return Err(eyre!(
+            "{} contracts regressed by more than {}%: {}",
+            regressed.len(),
+            args.threshold,
+            regressed.join(", ")
+        ));
+    }
+
+    info!("Diff command complete. No contract regressed beyond the threshold.");
+    Ok(())
+}
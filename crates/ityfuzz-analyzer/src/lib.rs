@@ -0,0 +1,16 @@
+//! Parsing and plotting of ityfuzz coverage logs. The `ityfuzz-analyzer`
+//! binary is a thin CLI over this library.
+
+pub mod clean;
+pub mod diff;
+pub mod plot;
+mod progress;
+pub mod prometheus;
+pub mod report;
+pub mod run;
+pub mod sqlite;
+pub mod types;
+
+pub use plot::{PlotOptions, aggregate_and_plot_data, read_stats_from_csv};
+pub use run::{ParseOptions, parse_log, write_csv};
+pub use types::StatsEntry;
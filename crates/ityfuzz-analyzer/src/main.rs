@@ -1,24 +1,32 @@
 use clap::Parser;
 use eyre::Result;
-use plot::handle_plot_command;
-use run::handle_run_command;
+use ityfuzz_analyzer::clean::handle_clean_command;
+use ityfuzz_analyzer::diff::handle_diff_command;
+use ityfuzz_analyzer::plot::handle_plot_command;
+use ityfuzz_analyzer::run::handle_run_command;
+use ityfuzz_analyzer::types::{Cli, Commands, ProgressMode};
 use std::env;
+use std::io;
 use tracing::{Level, info};
-use tracing_subscriber::FmtSubscriber;
-use types::{Cli, Commands};
-
-mod plot;
-mod run;
-mod types;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, registry};
 
 fn main() -> Result<()> {
+    let cli = Cli::parse();
+    cli.validate()?;
+
     // Create log file
-    let log_level = match env::var("LOG_LEVEL").unwrap_or_default().as_str() {
-        "trace" => Level::TRACE,
-        "debug" => Level::DEBUG,
-        "warn" => Level::WARN,
-        "error" => Level::ERROR,
-        _ => Level::INFO,
+    let log_level = if cli.verbose {
+        Level::DEBUG
+    } else {
+        match env::var("LOG_LEVEL").unwrap_or_default().as_str() {
+            "trace" => Level::TRACE,
+            "debug" => Level::DEBUG,
+            "warn" => Level::WARN,
+            "error" => Level::ERROR,
+            _ => Level::INFO,
+        }
     };
 
     let file_appender = tracing_appender::rolling::never(
@@ -31,27 +39,41 @@ fn main() -> Result<()> {
 
     let (non_blocking_appender, _guard) = tracing_appender::non_blocking(file_appender);
 
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(log_level)
+    let file_layer = fmt::layer()
         .with_file(true)
         .with_line_number(true)
         .with_writer(non_blocking_appender)
         .with_ansi(false)
-        .finish();
+        .with_filter(LevelFilter::from_level(log_level));
+    // With --quiet, errors are the only console output
+    let stderr_layer = cli.quiet.then(|| {
+        fmt::layer()
+            .with_writer(io::stderr)
+            .with_filter(LevelFilter::ERROR)
+    });
 
-    tracing::subscriber::set_global_default(subscriber)
+    tracing::subscriber::set_global_default(registry().with(file_layer).with(stderr_layer))
         .expect("Setting default tracing subscriber failed");
 
-    let cli = Cli::parse();
-
     match cli.command {
-        Commands::Run(args) => {
+        Commands::Run(mut args) => {
             info!("Executing 'run' command...");
-            handle_run_command(args)?;
+            if cli.quiet {
+                args.progress = ProgressMode::None;
+            }
+            handle_run_command(*args)?;
         }
         Commands::Plot(args) => {
             info!("Executing 'plot' command...");
-            handle_plot_command(args)?;
+            handle_plot_command(*args)?;
+        }
+        Commands::Clean(args) => {
+            info!("Executing 'clean' command...");
+            handle_clean_command(args)?;
+        }
+        Commands::Diff(args) => {
+            info!("Executing 'diff' command...");
+            handle_diff_command(args)?;
         }
     }
 
@@ -1,38 +1,505 @@
-use crate::types::{PlotArgs, StatsEntry};
-use csv::Reader; // Added Reader
+use crate::prometheus::write_prometheus_metrics;
+use crate::report::write_html_report;
+use crate::sqlite::load_stats_from_sqlite;
+use crate::types::{
+    Aggregate, AggregateMode, Align, BugEntry, GROUP_SEPARATOR, Metric, PlotArgs, PlotKind,
+    RunManifest, StatsEntry, XAxis, YUnit,
+};
+use csv::{ReaderBuilder, StringRecord};
 use eyre::{Result, WrapErr, eyre};
+use flate2::read::GzDecoder;
 use glob::glob;
+use plotters::coord::types::RangedCoordf64;
 use plotters::prelude::*;
-use plotters::style::text_anchor::{Pos, HPos, VPos};
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap};
 use std::fs::{self};
-use std::path::Path;
-use tracing::info;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+use tracing::{info, warn};
 // Added Deserialize
 
-fn read_stats_from_csv(csv_path: &Path) -> Result<Vec<StatsEntry>> {
-    let mut rdr = Reader::from_path(csv_path)
+/// Reads the stats entries of one contract from a CSV written by [`crate::run::write_csv`]
+/// Zero-byte and header-only files, e.g. left by a run killed mid-write,
+/// yield no entries. Columns are matched by header name, so CSVs of older
+/// versions that lack newer columns, or have extra ones, still load. CSVs
+/// with a `time_taken_nanos` column, as written by mau-analyzer, are
+/// converted to milliseconds.
+pub fn read_stats_from_csv(csv_path: &Path) -> Result<Vec<StatsEntry>> {
+    let file_len = fs::metadata(csv_path)
+        .wrap_err_with(|| format!("Failed to stat CSV file: {}", csv_path.display()))?
+        .len();
+    if file_len == 0 {
+        warn!("CSV file {} is empty, skipping", csv_path.display());
+        return Ok(Vec::new());
+    }
+
+    // Appending to a CSV of an older version adds rows with more columns
+    // than its header
+    let mut rdr = ReaderBuilder::new()
+        .comment(Some(b'#'))
+        .flexible(true)
+        .from_path(csv_path)
         .wrap_err_with(|| format!("Failed to open CSV file: {}", csv_path.display()))?;
+    let headers = rdr
+        .headers()
+        .wrap_err_with(|| format!("Failed to read CSV header of {}", csv_path.display()))?
+        .clone();
+    let (headers, nanos_column) =
+        rename_time_column(headers, "time_taken_nanos", "time_taken_millis");
     let mut entries = Vec::new();
-    for result in rdr.deserialize() {
-        let entry: StatsEntry = result.wrap_err_with(|| {
+    for result in rdr.records() {
+        let mut record = result
+            .wrap_err_with(|| format!("Failed to read record from {}", csv_path.display()))?;
+        if let Some(column) = nanos_column {
+            record = scale_time_field(&record, column, |nanos| nanos / 1_000_000);
+        }
+        let entry: StatsEntry = record.deserialize(Some(&headers)).wrap_err_with(|| {
             format!("Failed to deserialize record from {}", csv_path.display())
         })?;
         entries.push(entry);
     }
+    if entries.is_empty() {
+        warn!("CSV file {} has no records, skipping", csv_path.display());
+    }
+    // Files written with `run --append` hold several segments back to back.
+    // Only identical rows are dropped, `--time-resolution raw` keeps several
+    // samples per millisecond.
+    entries.sort_by_key(|e| e.time_taken_millis);
+    entries.dedup();
     Ok(entries)
 }
 
+/// Renames the `from` time column of `headers` to `to` unless `to` is
+/// present already, returning the column's index if it was renamed
+fn rename_time_column(
+    headers: StringRecord,
+    from: &str,
+    to: &str,
+) -> (StringRecord, Option<usize>) {
+    if headers.iter().any(|header| header == to) {
+        return (headers, None);
+    }
+    let Some(column) = headers.iter().position(|header| header == from) else {
+        return (headers, None);
+    };
+    let renamed = headers
+        .iter()
+        .map(|header| if header == from { to } else { header })
+        .collect();
+    (renamed, Some(column))
+}
+
+/// Applies `scale` to the time value in `column` of `record`. Values that
+/// are not numbers are kept, so deserializing reports them.
+fn scale_time_field(
+    record: &StringRecord,
+    column: usize,
+    scale: impl Fn(u64) -> u64,
+) -> StringRecord {
+    record
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match field.parse::<u64>() {
+            Ok(value) if i == column => scale(value).to_string(),
+            _ => field.to_string(),
+        })
+        .collect()
+}
+
+/// Options controlling how the aggregate plot is rendered
+pub struct PlotOptions {
+    /// Maximum number of points drawn per series, 0 disables downsampling
+    pub max_points: usize,
+    pub metric: Metric,
+    /// Moving-average window applied to the plotted series
+    pub smooth: Option<usize>,
+    pub y_unit: YUnit,
+    /// How repeated runs of the same contract are combined
+    pub aggregate: Aggregate,
+    /// How the values of different contracts are combined
+    pub aggregate_mode: AggregateMode,
+    /// Reference final coverage per contract, drawn as a baseline line
+    pub baseline: Option<HashMap<String, u64>>,
+    pub align: Align,
+    /// Write the per-contract values behind the aggregate line to a CSV
+    pub debug_aggregate: bool,
+    /// Only write the overall stats CSVs, not the chart
+    pub csv_only: bool,
+    pub plot_kind: PlotKind,
+    /// Stroke width of the data lines in pixels
+    pub line_width: u32,
+    /// Draw a marker at every plotted sample
+    pub markers: bool,
+    /// Maximum number of bands of a stacked plot, including `other`
+    pub max_bands: usize,
+    /// Fuzzer build the data came from, printed in the corner of the plot
+    pub fuzzer_version: Option<String>,
+    /// Fuzzing timeout drawn as a vertical line on the time axis
+    pub timeout_seconds: Option<u64>,
+    pub x_axis: XAxis,
+    /// Replace the computed chart caption and axis descriptions
+    pub caption: Option<String>,
+    pub x_label: Option<String>,
+    pub y_label: Option<String>,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        Self {
+            max_points: 5000,
+            metric: Metric::Instructions,
+            smooth: None,
+            y_unit: YUnit::Auto,
+            aggregate: Aggregate::Mean,
+            aggregate_mode: AggregateMode::Sum,
+            baseline: None,
+            align: Align::Zero,
+            debug_aggregate: false,
+            csv_only: false,
+            plot_kind: PlotKind::Line,
+            line_width: 1,
+            markers: false,
+            max_bands: 9,
+            fuzzer_version: None,
+            timeout_seconds: None,
+            x_axis: XAxis::Time,
+            caption: None,
+            x_label: None,
+            y_label: None,
+        }
+    }
+}
+
+impl YUnit {
+    /// Resolves `Auto` to a concrete unit for a maximum raw value
+    fn resolve(self, max_raw_value: f64) -> YUnit {
+        match self {
+            YUnit::Auto if max_raw_value >= 1_000_000.0 => YUnit::Millions,
+            YUnit::Auto if max_raw_value >= 1_000.0 => YUnit::Thousands,
+            YUnit::Auto => YUnit::Raw,
+            unit => unit,
+        }
+    }
+
+    fn divisor(self) -> f64 {
+        match self {
+            YUnit::Auto | YUnit::Raw => 1.0,
+            YUnit::Thousands => 1_000.0,
+            YUnit::Millions => 1_000_000.0,
+        }
+    }
+
+    fn label_suffix(self) -> &'static str {
+        match self {
+            YUnit::Auto | YUnit::Raw => "",
+            YUnit::Thousands => " / 10^3",
+            YUnit::Millions => " / 10^6",
+        }
+    }
+}
+
+/// Colors of contract and group series: the Tableau 10 palette without its
+/// red, which is used for the overall line
+const SERIES_COLORS: [RGBColor; 9] = [
+    RGBColor(31, 119, 180),
+    RGBColor(255, 127, 14),
+    RGBColor(44, 160, 44),
+    RGBColor(148, 103, 189),
+    RGBColor(140, 86, 75),
+    RGBColor(227, 119, 194),
+    RGBColor(127, 127, 127),
+    RGBColor(188, 189, 34),
+    RGBColor(23, 190, 207),
+];
+
+/// Color of the series of a contract or group, derived from a stable hash
+/// (FNV-1a) of its id so it is the same in every plot and on every run
+fn series_color(id: &str) -> RGBColor {
+    let hash = id.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    });
+    SERIES_COLORS[(hash % SERIES_COLORS.len() as u64) as usize]
+}
+
+/// Contract id of a run id, stripping the `.run<k>` suffix added by `--repeat`
+fn repetition_base_id(run_id: &str) -> &str {
+    split_run_id(run_id).0
+}
+
+/// Group line a run is plotted in. Runs of a `--matrix` sweep, stored as
+/// `<contract>__<combo>`, are grouped by option combination; contracts found
+/// below `--depth` > 1 are prefixed with their group.
+fn series_group(run_id: &str) -> Option<&str> {
+    let (base, _) = split_run_id(run_id);
+    match base.rsplit_once(GROUP_SEPARATOR) {
+        Some((_, combo)) if combo.contains('=') => Some(combo),
+        _ => run_id.split_once(GROUP_SEPARATOR).map(|(group, _)| group),
+    }
+}
+
+/// Splits a run id into its contract id and repetition number, 0 for runs
+/// without a `.run<k>` suffix
+pub fn split_run_id(run_id: &str) -> (&str, u32) {
+    match run_id.rsplit_once(".run") {
+        Some((base, k)) if !k.is_empty() && k.bytes().all(|b| b.is_ascii_digit()) => {
+            (base, k.parse().unwrap_or(0))
+        }
+        _ => (run_id, 0),
+    }
+}
+
+/// Linearly interpolates `value` of a time-sorted run at `time_millis`. The
+/// run has no coverage before its first sample and keeps its last value after
+/// its final sample.
+fn interpolate_at(entries: &[StatsEntry], time_millis: u64, value: fn(&StatsEntry) -> f64) -> f64 {
+    let idx = entries.partition_point(|e| e.time_taken_millis <= time_millis);
+    if idx == 0 {
+        return 0.0;
+    }
+    let prev = &entries[idx - 1];
+    match entries.get(idx) {
+        Some(next) => {
+            let span = (next.time_taken_millis - prev.time_taken_millis) as f64;
+            let fraction = (time_millis - prev.time_taken_millis) as f64 / span;
+            value(prev) + (value(next) - value(prev)) * fraction
+        }
+        None => value(prev),
+    }
+}
+
+fn aggregate_values(values: &mut [f64], aggregate: Aggregate) -> f64 {
+    match aggregate {
+        Aggregate::Mean => values.iter().sum::<f64>() / values.len() as f64,
+        Aggregate::Median => {
+            values.sort_by(f64::total_cmp);
+            let mid = values.len() / 2;
+            if values.len() % 2 == 0 {
+                (values[mid - 1] + values[mid]) / 2.0
+            } else {
+                values[mid]
+            }
+        }
+        Aggregate::Best => values.iter().copied().fold(f64::MIN, f64::max),
+        Aggregate::Worst => values.iter().copied().fold(f64::MAX, f64::min),
+    }
+}
+
+/// Combines several runs of one contract on the union of their timestamps
+fn combine_runs(runs: &[&Vec<StatsEntry>], aggregate: Aggregate) -> Vec<StatsEntry> {
+    let runs: Vec<Vec<StatsEntry>> = runs
+        .iter()
+        .map(|run| {
+            let mut run = run.to_vec();
+            run.sort_by_key(|e| e.time_taken_millis);
+            run
+        })
+        .collect();
+
+    let mut timestamps: Vec<u64> = runs
+        .iter()
+        .flat_map(|run| run.iter().map(|e| e.time_taken_millis))
+        .collect();
+    timestamps.sort_unstable();
+    timestamps.dedup();
+
+    let total_instructions = runs
+        .iter()
+        .filter_map(|run| run.last().map(|e| e.total_instructions))
+        .max()
+        .unwrap_or(0);
+    let total_branches = runs
+        .iter()
+        .filter_map(|run| run.last().map(|e| e.total_branches))
+        .max()
+        .unwrap_or(0);
+
+    let has_reported_percent = runs
+        .iter()
+        .any(|run| run.iter().any(|e| e.coverage_percent.is_some()));
+
+    timestamps
+        .into_iter()
+        .map(|time_taken_millis| {
+            let mut instructions: Vec<f64> = runs
+                .iter()
+                .map(|run| {
+                    interpolate_at(run, time_taken_millis, |e| e.instructions_covered as f64)
+                })
+                .collect();
+            let mut branches: Vec<f64> = runs
+                .iter()
+                .map(|run| interpolate_at(run, time_taken_millis, |e| e.branches_covered as f64))
+                .collect();
+            let coverage_percent = has_reported_percent.then(|| {
+                let mut percents: Vec<f64> = runs
+                    .iter()
+                    .map(|run| {
+                        interpolate_at(run, time_taken_millis, |e| {
+                            e.coverage_percent.unwrap_or(0.0)
+                        })
+                    })
+                    .collect();
+                aggregate_values(&mut percents, aggregate)
+            });
+            StatsEntry {
+                instructions_covered: aggregate_values(&mut instructions, aggregate).round() as u64,
+                branches_covered: aggregate_values(&mut branches, aggregate).round() as u64,
+                total_instructions,
+                time_taken_millis,
+                total_branches,
+                coverage_percent,
+                abs_timestamp_millis: None,
+                execs: None,
+            }
+        })
+        .collect()
+}
+
+/// Merges repeated runs (`<contract>.run<k>`) into one series per contract
+pub fn combine_repetitions(
+    all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
+    aggregate: Aggregate,
+) -> HashMap<String, Vec<StatsEntry>> {
+    let mut runs_by_contract: HashMap<&str, Vec<&Vec<StatsEntry>>> = HashMap::new();
+    for (run_id, entries) in all_contract_stats {
+        runs_by_contract
+            .entry(repetition_base_id(run_id))
+            .or_default()
+            .push(entries);
+    }
+
+    runs_by_contract
+        .into_iter()
+        .map(|(contract_id, runs)| {
+            let entries = if runs.len() == 1 {
+                runs[0].clone()
+            } else {
+                info!(
+                    "Combining {} runs of {} using {:?}",
+                    runs.len(),
+                    contract_id,
+                    aggregate
+                );
+                combine_runs(&runs, aggregate)
+            };
+            (contract_id.to_string(), entries)
+        })
+        .collect()
+}
+
+/// Shifts each contract's samples so that its first sample is at t=0
+fn align_to_first_sample(all_contract_stats: &mut HashMap<String, Vec<StatsEntry>>) {
+    for entries in all_contract_stats.values_mut() {
+        let Some(first_millis) = entries.iter().map(|e| e.time_taken_millis).min() else {
+            continue;
+        };
+        for entry in entries.iter_mut() {
+            entry.time_taken_millis -= first_millis;
+        }
+    }
+}
+
+/// Differentiates a cumulative `(time, value)` series into `(time, delta value / delta time)`.
+/// Consecutive points sharing a timestamp are skipped to avoid dividing by zero.
+fn coverage_rate(data: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    data.windows(2)
+        .filter_map(|w| {
+            let (t0, v0) = w[0];
+            let (t1, v1) = w[1];
+            let dt = t1 - t0;
+            if dt > 0.0 {
+                Some((t1, (v1 - v0) / dt))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Trailing moving average over `window` samples.
+fn smooth_series(data: &[(f64, f64)], window: usize) -> Vec<(f64, f64)> {
+    if window <= 1 {
+        return data.to_vec();
+    }
+    let mut sum = 0.0;
+    data.iter()
+        .enumerate()
+        .map(|(i, &(t, v))| {
+            sum += v;
+            if i >= window {
+                sum -= data[i - window].1;
+            }
+            (t, sum / (i + 1).min(window) as f64)
+        })
+        .collect()
+}
+
+/// Downsamples `data` to at most `threshold` points using the
+/// Largest-Triangle-Three-Buckets algorithm, keeping the first and last points.
+fn downsample_lttb(data: &[(f64, f64)], threshold: usize) -> Vec<(f64, f64)> {
+    if threshold == 0 || threshold >= data.len() || threshold < 3 {
+        return data.to_vec();
+    }
+
+    let mut sampled = Vec::with_capacity(threshold);
+    let bucket_size = (data.len() - 2) as f64 / (threshold - 2) as f64;
+    let mut a = 0;
+    sampled.push(data[a]);
+
+    for i in 0..threshold - 2 {
+        // Average point of the next bucket, used as the third triangle vertex
+        let next_start = ((i + 1) as f64 * bucket_size) as usize + 1;
+        let next_end = (((i + 2) as f64 * bucket_size) as usize + 1).min(data.len());
+        let next_bucket = &data[next_start..next_end];
+        let (avg_x, avg_y) = next_bucket
+            .iter()
+            .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+        let avg_x = avg_x / next_bucket.len() as f64;
+        let avg_y = avg_y / next_bucket.len() as f64;
+
+        let start = (i as f64 * bucket_size) as usize + 1;
+        let end = next_start;
+        let (ax, ay) = data[a];
+        let mut max_area = -1.0;
+        let mut max_index = start;
+        for (j, (x, y)) in data.iter().enumerate().take(end).skip(start) {
+            let area = ((ax - avg_x) * (y - ay) - (ax - x) * (avg_y - ay)).abs();
+            if area > max_area {
+                max_area = area;
+                max_index = j;
+            }
+        }
+        sampled.push(data[max_index]);
+        a = max_index;
+    }
+
+    sampled.push(data[data.len() - 1]);
+    sampled
+}
+
 pub fn aggregate_and_plot_data(
     all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
     plot_output_dir: &Path,
     title_prefix: Option<String>,
+    options: &PlotOptions,
 ) -> Result<()> {
+    if options.metric == Metric::Bugs {
+        let all_contract_bugs = load_contract_bugs(plot_output_dir)?;
+        return plot_bugs(&all_contract_bugs, plot_output_dir, title_prefix, options);
+    }
     if all_contract_stats.is_empty() {
         info!("No data to plot.");
         return Ok(());
     }
 
+    let all_contract_stats = &prepare_stats(all_contract_stats, options);
+
     let title_prefix = title_prefix.unwrap_or_else(|| {
         plot_output_dir
             .file_name()
@@ -41,204 +508,1420 @@ pub fn aggregate_and_plot_data(
             .to_string()
     });
 
-    let mut aggregated_instructions_over_time: BTreeMap<u64, u64> = BTreeMap::new();
-    let mut all_timestamps: Vec<u64> = Vec::new();
+    let all_timestamps = collect_timestamps(all_contract_stats);
+    if all_timestamps.is_empty() {
+        info!("No timestamps found in data. Skipping plot.");
+        return Ok(());
+    }
+
+    // total instructions in all contracts, combined like the covered instructions
+    let contract_totals: Vec<u64> = all_contract_stats
+        .values()
+        .filter_map(|stats_vec| stats_vec.last().map(|last| last.total_instructions))
+        .collect();
+    let total_instructions = combine_contract_values(&contract_totals, options.aggregate_mode);
+
+    // Fuzzers that only report percentages have no instruction counts to plot
+    let has_reported_percent = all_contract_stats
+        .values()
+        .any(|stats_vec| stats_vec.iter().any(|e| e.coverage_percent.is_some()));
+    let metric = if total_instructions == 0.0
+        && has_reported_percent
+        && options.metric != Metric::CoveragePercent
+    {
+        info!("No instruction counts found, plotting the reported coverage percentage instead");
+        Metric::CoveragePercent
+    } else {
+        options.metric
+    };
+
+    let plot_data = aggregate(all_contract_stats, options.aggregate_mode);
+
+    let mut stats_by_group: BTreeMap<&str, Vec<&Vec<StatsEntry>>> = BTreeMap::new();
+    for (contract_id, stats_vec) in all_contract_stats {
+        if let Some(group) = series_group(contract_id) {
+            stats_by_group.entry(group).or_default().push(stats_vec);
+        }
+    }
+    // (group, aggregated data, total instructions in thousands, group stats)
+    let group_plot_data: Vec<_> = if stats_by_group.len() > 1 {
+        stats_by_group
+            .iter()
+            .map(|(group, stats)| {
+                let group_totals: Vec<u64> = stats
+                    .iter()
+                    .filter_map(|stats_vec| stats_vec.last().map(|last| last.total_instructions))
+                    .collect();
+                let group_total_k =
+                    combine_contract_values(&group_totals, options.aggregate_mode) / 1000.0;
+                (
+                    *group,
+                    aggregate_instructions(stats, &all_timestamps, options.aggregate_mode),
+                    group_total_k,
+                    stats,
+                )
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    if plot_data.is_empty() {
+        info!("Aggregated plot data is empty. Skipping plot generation.");
+        return Ok(());
+    }
+
+    // store the overall csv stats
+    let total_instructions_k = total_instructions / 1000.0;
+    store_overall_stats_csv(
+        plot_output_dir,
+        &title_prefix,
+        options.x_axis,
+        all_contract_stats.len(),
+        total_instructions_k,
+        &plot_data,
+    )?;
+    // The simplified CSV samples fixed points in time
+    if options.x_axis == XAxis::Time {
+        store_simplified_stats_csv(
+            plot_output_dir,
+            &title_prefix,
+            all_contract_stats.len(),
+            total_instructions_k,
+            &plot_data,
+        )?;
+    }
+    if options.debug_aggregate {
+        store_aggregate_debug_csv(
+            plot_output_dir,
+            &title_prefix,
+            options.x_axis,
+            all_contract_stats,
+            &all_timestamps,
+        )?;
+    }
+    if options.csv_only {
+        info!("Skipping the chart (--csv-only)");
+        return Ok(());
+    }
+    if options.plot_kind == PlotKind::Stacked {
+        if metric != Metric::Instructions {
+            return Err(eyre!(
+                "--plot-kind stacked only supports the instructions metric, not {:?}",
+                metric
+            ));
+        }
+        return plot_stacked(
+            all_contract_stats,
+            &all_timestamps,
+            plot_output_dir,
+            &title_prefix,
+            options,
+        );
+    }
+
+    let (caption, plot_file_suffix) = match metric {
+        Metric::Instructions => (
+            "Overall Instructions Covered",
+            "overall_instructions_plot.png",
+        ),
+        Metric::Rate => ("Overall Coverage Rate", "overall_coverage_rate_plot.png"),
+        Metric::CoveragePercent => ("Overall Coverage (%)", "overall_coverage_percent_plot.png"),
+        // Handled by `plot_bugs` above
+        Metric::Bugs => unreachable!("bugs are plotted by plot_bugs"),
+    };
+    let x_name = match options.x_axis {
+        XAxis::Time => "Time",
+        XAxis::Execs => "Executions",
+    };
+    let to_series = |data: &[(f64, f64)], total_k: f64, stats: &[&Vec<StatsEntry>]| {
+        let series = match metric {
+            Metric::Instructions => data.to_vec(),
+            Metric::Rate => coverage_rate(data),
+            Metric::CoveragePercent if total_k > 0.0 => data
+                .iter()
+                .map(|&(t, v)| (t, v / total_k * 100.0))
+                .collect(),
+            // No instruction counts, use the percentages reported by the fuzzer
+            Metric::CoveragePercent => {
+                aggregate_percent(stats, &all_timestamps, options.aggregate_mode)
+            }
+            Metric::Bugs => unreachable!("bugs are plotted by plot_bugs"),
+        };
+        match options.smooth {
+            Some(window) => smooth_series(&series, window),
+            None => series,
+        }
+    };
+    let all_stats: Vec<&Vec<StatsEntry>> = all_contract_stats.values().collect();
+    let series = to_series(&plot_data, total_instructions_k, &all_stats);
+    if series.is_empty() {
+        info!(
+            "Not enough data points to plot {:?}. Skipping plot generation.",
+            metric
+        );
+        return Ok(());
+    }
+
+    // The aggregated data is in thousands of instructions, rescale it to the y axis unit.
+    // Percentages are plotted as is.
+    let is_percent = metric == Metric::CoveragePercent;
+    let max_raw_value = series.iter().map(|(_, v)| *v).fold(0.0_f64, f64::max) * 1000.0;
+    let y_unit = options.y_unit.resolve(max_raw_value);
+    let unit_scale = if is_percent {
+        1.0
+    } else {
+        1000.0 / y_unit.divisor()
+    };
+    let series: Vec<(f64, f64)> = series
+        .into_iter()
+        .map(|(t, v)| (t, v * unit_scale))
+        .collect();
+    let group_series: Vec<(&str, Vec<(f64, f64)>)> = group_plot_data
+        .iter()
+        .map(|(group, data, group_total_k, stats)| {
+            let scaled = to_series(data, *group_total_k, stats)
+                .into_iter()
+                .map(|(t, v)| (t, v * unit_scale))
+                .collect();
+            (*group, scaled)
+        })
+        .collect();
+    let total_instructions_scaled = total_instructions_k * unit_scale;
+    // Reference line: baseline final coverage of the plotted contracts
+    let baseline_scaled = match (&options.baseline, metric) {
+        (Some(baseline), Metric::Instructions) => {
+            let values: Vec<u64> = all_contract_stats
+                .keys()
+                .filter_map(|contract_id| baseline.get(contract_id).copied())
+                .collect();
+            if values.is_empty() {
+                info!("No plotted contract has a baseline value, skipping baseline line");
+                None
+            } else {
+                let baseline_k = combine_contract_values(&values, options.aggregate_mode) / 1000.0;
+                Some(baseline_k * unit_scale)
+            }
+        }
+        _ => None,
+    };
+    let show_labels = !group_series.is_empty() || baseline_scaled.is_some();
+    let y_desc = match metric {
+        Metric::Instructions => format!("Number of Instructions{}", y_unit.label_suffix()),
+        Metric::Rate if options.x_axis == XAxis::Execs => {
+            format!("Instructions{} per 1000 Executions", y_unit.label_suffix())
+        }
+        Metric::Rate => format!("Instructions{} per Second", y_unit.label_suffix()),
+        Metric::CoveragePercent => "Coverage (%)".to_string(),
+        Metric::Bugs => unreachable!("bugs are plotted by plot_bugs"),
+    };
+
+    let plot_path = plot_output_dir.join(format!(
+        "{}_{}",
+        overall_file_prefix(&title_prefix, options.x_axis),
+        plot_file_suffix
+    ));
+
+    let temp_plot_path = temp_path(&plot_path);
+    let root_area = BitMapBackend::new(&temp_plot_path, (1024, 768)).into_drawing_area();
+    root_area
+        .fill(&WHITE)
+        .wrap_err("Failed to fill plot background")?;
+
+    let max_time_seconds = series.iter().map(|(t, _)| *t).fold(0.0_f64, f64::max) * 1.1;
+    let max_value = series.iter().map(|(_, v)| *v).fold(0.0_f64, f64::max) * 1.1;
+    let show_total_line = metric == Metric::Instructions;
+
+    let x_axis_max = if max_time_seconds > 0.0 {
+        max_time_seconds
+    } else {
+        1.0
+    };
+    // Keep the timeout line inside the chart
+    let x_axis_max = match options.timeout_seconds {
+        Some(timeout) => x_axis_max.max(timeout as f64 * 1.05),
+        None => x_axis_max,
+    };
+    // Add 10% padding above the total instructions line; percentages stop
+    // just above 100
+    let y_axis_max = if max_value <= 0.0 {
+        1.0
+    } else if is_percent {
+        max_value.min(105.0)
+    } else if show_total_line {
+        f64::max(max_value, total_instructions_scaled).max(baseline_scaled.unwrap_or(0.0)) * 1.1
+    } else {
+        max_value
+    };
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .caption(
+            options
+                .caption
+                .clone()
+                .unwrap_or_else(|| format!("{} {} vs. {}", title_prefix, caption, x_name)),
+            ("sans-serif", 30).into_font(),
+        )
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0.0..x_axis_max, 0.0..y_axis_max)
+        .wrap_err("Failed to build chart")?;
+
+    chart
+        .configure_mesh()
+        .x_desc(options.x_label.as_deref().unwrap_or(options.x_axis.label()))
+        .y_desc(options.y_label.as_deref().unwrap_or(&y_desc))
+        .draw()
+        .wrap_err("Failed to draw chart mesh")?;
+
+    let drawn_data = downsample_lttb(&series, options.max_points);
+    if drawn_data.len() < series.len() {
+        info!(
+            "Downsampled plot series from {} to {} points",
+            series.len(),
+            drawn_data.len()
+        );
+    }
+
+    // Draw the coverage progress line
+    let line_width = options.line_width;
+    let overall_line = chart
+        .draw_series(LineSeries::new(
+            drawn_data.iter().copied(),
+            RED.stroke_width(line_width),
+        ))
+        .wrap_err("Failed to draw data series on chart")?;
+    if show_labels {
+        overall_line.label("all");
+    }
+    overall_line.legend(move |(x, y)| {
+        PathElement::new(vec![(x, y), (x + 20, y)], RED.stroke_width(line_width))
+    });
+    if options.markers {
+        draw_markers(&mut chart, &drawn_data, RED, line_width)?;
+    }
+
+    // One line per benchmark group
+    for (group, group_data) in &group_series {
+        let color = series_color(group);
+        let group_data = downsample_lttb(group_data, options.max_points);
+        chart
+            .draw_series(LineSeries::new(
+                group_data.iter().copied(),
+                color.stroke_width(line_width),
+            ))
+            .wrap_err_with(|| format!("Failed to draw series for group {}", group))?
+            .label(*group)
+            .legend(move |(x, y)| {
+                PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(line_width))
+            });
+        if options.markers {
+            draw_markers(&mut chart, &group_data, color, line_width)?;
+        }
+    }
+
+    // Draw horizontal line for total instructions
+    if show_total_line {
+        let total_line = chart.draw_series(DashedLineSeries::new(
+            vec![
+                (0.0, total_instructions_scaled),
+                (x_axis_max, total_instructions_scaled),
+            ],
+            5,
+            10,
+            ShapeStyle {
+                color: BLACK.mix(1.0),
+                filled: false,
+                stroke_width: 1,
+            },
+        ))?;
+        if show_labels {
+            total_line.label("total");
+        }
+        total_line.legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE.mix(0.5)));
+    }
+
+    if let Some(baseline_value) = baseline_scaled {
+        let baseline_color = BLUE.mix(0.4);
+        chart
+            .draw_series(DashedLineSeries::new(
+                vec![(0.0, baseline_value), (x_axis_max, baseline_value)],
+                10,
+                5,
+                baseline_color.stroke_width(2),
+            ))
+            .wrap_err("Failed to draw baseline line")?
+            .label("baseline")
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], baseline_color));
+    }
+
+    if let Some(timeout) = options.timeout_seconds {
+        let timeout_color = BLACK.mix(0.4);
+        let timeout = timeout as f64;
+        chart
+            .draw_series(DashedLineSeries::new(
+                vec![(timeout, 0.0), (timeout, y_axis_max)],
+                8,
+                4,
+                timeout_color.stroke_width(2),
+            ))
+            .wrap_err("Failed to draw timeout line")?
+            .label("timeout")
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], timeout_color));
+    }
+
+    // Configure the legend
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    draw_fuzzer_version(&root_area, options)?;
+    root_area.present().wrap_err("Failed to present chart")?;
+    check_plot_written(&temp_plot_path)?;
+    rename_into_place(&temp_plot_path, &plot_path)?;
+    info!("Plot saved to {}", plot_path.display());
+
+    Ok(())
+}
+
+/// Draws a filled circle at every point of `data`, a bit wider than the line
+fn draw_markers(
+    chart: &mut ChartContext<'_, BitMapBackend<'_>, Cartesian2d<RangedCoordf64, RangedCoordf64>>,
+    data: &[(f64, f64)],
+    color: RGBColor,
+    line_width: u32,
+) -> Result<()> {
+    chart
+        .draw_series(
+            data.iter()
+                .map(|&point| Circle::new(point, line_width + 2, color.filled())),
+        )
+        .wrap_err("Failed to draw markers")?;
+    Ok(())
+}
+
+/// Color of the `other` band of a stacked plot
+const OTHER_BAND_COLOR: RGBColor = RGBColor(199, 199, 199);
+
+/// Draws each contract's covered instructions as a filled band on top of the
+/// bands below it. Bands are ordered by final coverage, largest at the bottom;
+/// contracts beyond `options.max_bands - 1` are summed into an `other` band.
+fn plot_stacked(
+    all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
+    timestamps: &[u64],
+    plot_output_dir: &Path,
+    title_prefix: &str,
+    options: &PlotOptions,
+) -> Result<()> {
+    let final_coverage = |contract_id: &str| {
+        all_contract_stats[contract_id]
+            .last()
+            .map_or(0, |e| e.instructions_covered)
+    };
+    let mut contract_ids: Vec<&str> = all_contract_stats.keys().map(String::as_str).collect();
+    // Ties broken by id so the order is the same on every run
+    contract_ids.sort_by(|a, b| {
+        final_coverage(b)
+            .cmp(&final_coverage(a))
+            .then_with(|| a.cmp(b))
+    });
+
+    let max_bands = options.max_bands.max(2);
+    let num_kept = if contract_ids.len() > max_bands {
+        info!(
+            "Summing the {} contracts with the least coverage into the 'other' band",
+            contract_ids.len() - (max_bands - 1)
+        );
+        max_bands - 1
+    } else {
+        contract_ids.len()
+    };
+    let mut band_labels: Vec<&str> = contract_ids[..num_kept].to_vec();
+    if num_kept < contract_ids.len() {
+        band_labels.push("other");
+    }
+
+    // A band polygon needs no more points than a line would
+    let step = match options.max_points {
+        0 => 1,
+        max_points => timestamps.len().div_ceil(max_points).max(1),
+    };
+    let mut times: Vec<u64> = timestamps.iter().copied().step_by(step).collect();
+    if times.last() != timestamps.last() {
+        times.extend(timestamps.last());
+    }
+
+    // Upper edge of every band at each time
+    let contract_stats: Vec<&Vec<StatsEntry>> = contract_ids
+        .iter()
+        .map(|contract_id| &all_contract_stats[*contract_id])
+        .collect();
+    let mut latest = LatestValues::new(&contract_stats, |e| e.instructions_covered as f64);
+    let mut upper_edges: Vec<Vec<f64>> = vec![Vec::with_capacity(times.len()); band_labels.len()];
+    for &ts_millis in &times {
+        latest.advance_to(ts_millis);
+        let mut cumulative = 0.0;
+        for (band, edges) in upper_edges.iter_mut().enumerate() {
+            cumulative += if band < num_kept {
+                latest.latest[band]
+            } else {
+                latest.latest[num_kept..].iter().sum()
+            };
+            edges.push(cumulative);
+        }
+    }
+
+    let max_value = upper_edges
+        .last()
+        .map_or(0.0, |edges| edges.iter().copied().fold(0.0, f64::max));
+    let y_unit = options.y_unit.resolve(max_value);
+    let divisor = y_unit.divisor();
+    let x_axis_max = match times.last() {
+        Some(&last) if last > 0 => last as f64 / 1_000.0 * 1.1,
+        _ => 1.0,
+    };
+    let y_axis_max = if max_value > 0.0 {
+        max_value / divisor * 1.1
+    } else {
+        1.0
+    };
+
+    let plot_path = plot_output_dir.join(format!(
+        "{}_overall_instructions_stacked_plot.png",
+        title_prefix
+    ));
+    let temp_plot_path = temp_path(&plot_path);
+    let root_area = BitMapBackend::new(&temp_plot_path, (1024, 768)).into_drawing_area();
+    root_area
+        .fill(&WHITE)
+        .wrap_err("Failed to fill plot background")?;
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .caption(
+            options.caption.clone().unwrap_or_else(|| {
+                format!(
+                    "{} Instructions Covered per Contract vs. Time",
+                    title_prefix
+                )
+            }),
+            ("sans-serif", 30).into_font(),
+        )
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0.0..x_axis_max, 0.0..y_axis_max)
+        .wrap_err("Failed to build chart")?;
+
+    chart
+        .configure_mesh()
+        .x_desc(options.x_label.as_deref().unwrap_or("Time (seconds)"))
+        .y_desc(
+            options
+                .y_label
+                .clone()
+                .unwrap_or_else(|| format!("Number of Instructions{}", y_unit.label_suffix())),
+        )
+        .draw()
+        .wrap_err("Failed to draw chart mesh")?;
+
+    let zero_edges = vec![0.0; times.len()];
+    for (band, label) in band_labels.iter().enumerate() {
+        let lower = if band == 0 {
+            &zero_edges
+        } else {
+            &upper_edges[band - 1]
+        };
+        let upper = &upper_edges[band];
+        let points: Vec<(f64, f64)> = times
+            .iter()
+            .zip(upper)
+            .chain(times.iter().zip(lower).rev())
+            .map(|(&ts_millis, &value)| (ts_millis as f64 / 1_000.0, value / divisor))
+            .collect();
+        // Neighbouring bands need distinct colors, which hashing the id cannot promise
+        let color = if band < num_kept {
+            SERIES_COLORS[band % SERIES_COLORS.len()]
+        } else {
+            OTHER_BAND_COLOR
+        };
+        chart
+            .draw_series(std::iter::once(Polygon::new(points, color.filled())))
+            .wrap_err_with(|| format!("Failed to draw band of {}", label))?
+            .label(*label)
+            .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], color.filled()));
+    }
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    draw_fuzzer_version(&root_area, options)?;
+    root_area.present().wrap_err("Failed to present chart")?;
+    check_plot_written(&temp_plot_path)?;
+    rename_into_place(&temp_plot_path, &plot_path)?;
+    info!("Stacked plot saved to {}", plot_path.display());
+    Ok(())
+}
+
+/// Number of bugs a run had found by `time_millis`
+fn bugs_at(entries: &[BugEntry], time_millis: u64) -> f64 {
+    let idx = entries.partition_point(|e| e.time_taken_millis <= time_millis);
+    idx.checked_sub(1)
+        .map_or(0.0, |idx| entries[idx].bugs_found as f64)
+}
+
+/// Draws the cumulative number of bugs found over time as a step plot and
+/// stores the series in `<title>_overall_bugs_stats.csv`. Repetitions are
+/// combined with `options.aggregate`, contracts with `options.aggregate_mode`.
+/// Contracts without any bug have no bugs CSV and are not counted.
+fn plot_bugs(
+    all_contract_bugs: &HashMap<String, Vec<BugEntry>>,
+    plot_output_dir: &Path,
+    title_prefix: Option<String>,
+    options: &PlotOptions,
+) -> Result<()> {
+    if all_contract_bugs.is_empty() {
+        info!("No bugs found in any contract. Skipping bugs plot.");
+        return Ok(());
+    }
+    let title_prefix = title_prefix.unwrap_or_else(|| {
+        plot_output_dir
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    });
+
+    let mut runs_by_contract: BTreeMap<&str, Vec<&Vec<BugEntry>>> = BTreeMap::new();
+    for (run_id, entries) in all_contract_bugs {
+        runs_by_contract
+            .entry(repetition_base_id(run_id))
+            .or_default()
+            .push(entries);
+    }
+    let mut timestamps: Vec<u64> = all_contract_bugs
+        .values()
+        .flat_map(|entries| entries.iter().map(|e| e.time_taken_millis))
+        .chain([0])
+        .collect();
+    timestamps.sort_unstable();
+    timestamps.dedup();
+
+    let data: Vec<(f64, f64)> = timestamps
+        .iter()
+        .map(|&ts_millis| {
+            let values: Vec<f64> = runs_by_contract
+                .values()
+                .map(|runs| {
+                    let mut run_values: Vec<f64> = runs
+                        .iter()
+                        .map(|entries| bugs_at(entries, ts_millis))
+                        .collect();
+                    aggregate_values(&mut run_values, options.aggregate)
+                })
+                .collect();
+            let bugs = match options.aggregate_mode {
+                AggregateMode::Sum => values.iter().sum(),
+                AggregateMode::Max => values.iter().copied().fold(0.0, f64::max),
+                AggregateMode::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            };
+            (ts_millis as f64 / 1_000.0, bugs)
+        })
+        .collect();
+
+    let csv_path = plot_output_dir.join(format!("{}_overall_bugs_stats.csv", title_prefix));
+    let mut wtr = create_overall_csv(
+        &csv_path,
+        &title_prefix,
+        XAxis::Time,
+        runs_by_contract.len(),
+    )?;
+    wtr.write_record(["time_seconds", "bugs"])
+        .wrap_err("Failed to write CSV header")?;
+    for (time_seconds, bugs) in &data {
+        wtr.write_record([time_seconds.to_string(), bugs.to_string()])
+            .wrap_err("Failed to write CSV record")?;
+    }
+    finish_overall_csv(wtr, &csv_path)?;
+    if options.csv_only {
+        info!("Skipping the bugs chart (--csv-only)");
+        return Ok(());
+    }
+
+    // Hold each count until the next bug is found
+    let mut steps: Vec<(f64, f64)> = Vec::with_capacity(data.len() * 2);
+    for &(time_seconds, bugs) in &data {
+        if let Some(&(_, previous)) = steps.last() {
+            steps.push((time_seconds, previous));
+        }
+        steps.push((time_seconds, bugs));
+    }
+
+    let plot_path = plot_output_dir.join(format!("{}_overall_bugs_plot.png", title_prefix));
+    let temp_plot_path = temp_path(&plot_path);
+    let root_area = BitMapBackend::new(&temp_plot_path, (1024, 768)).into_drawing_area();
+    root_area
+        .fill(&WHITE)
+        .wrap_err("Failed to fill plot background")?;
+
+    let max_time_seconds = steps.iter().map(|(t, _)| *t).fold(0.0_f64, f64::max) * 1.1;
+    let max_bugs = steps.iter().map(|(_, v)| *v).fold(0.0_f64, f64::max) * 1.1;
+    let x_axis_max = if max_time_seconds > 0.0 {
+        max_time_seconds
+    } else {
+        1.0
+    };
+    let y_axis_max = if max_bugs > 0.0 { max_bugs } else { 1.0 };
+    // Extend the last count to the right edge of the chart
+    if let Some(&(_, last)) = steps.last() {
+        steps.push((x_axis_max, last));
+    }
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .caption(
+            options
+                .caption
+                .clone()
+                .unwrap_or_else(|| format!("{} Overall Bugs Found vs. Time", title_prefix)),
+            ("sans-serif", 30).into_font(),
+        )
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0.0..x_axis_max, 0.0..y_axis_max)
+        .wrap_err("Failed to build chart")?;
+    chart
+        .configure_mesh()
+        .x_desc(options.x_label.as_deref().unwrap_or("Time (seconds)"))
+        .y_desc(options.y_label.as_deref().unwrap_or("Bugs Found"))
+        .draw()
+        .wrap_err("Failed to draw chart mesh")?;
+    chart
+        .draw_series(LineSeries::new(steps, RED.stroke_width(options.line_width)))
+        .wrap_err("Failed to draw data series on chart")?;
+    // Only at the counts themselves, not the corners of the steps
+    if options.markers {
+        draw_markers(&mut chart, &data, RED, options.line_width)?;
+    }
+
+    draw_fuzzer_version(&root_area, options)?;
+    root_area.present().wrap_err("Failed to present chart")?;
+    check_plot_written(&temp_plot_path)?;
+    rename_into_place(&temp_plot_path, &plot_path)?;
+    info!("Plot saved to {}", plot_path.display());
+    Ok(())
+}
+
+/// Reads the cumulative bug counts of one contract run from a CSV written by
+/// the `run` command
+fn read_bugs_from_csv(csv_path: &Path) -> Result<Vec<BugEntry>> {
+    let mut rdr = ReaderBuilder::new()
+        .comment(Some(b'#'))
+        .from_path(csv_path)
+        .wrap_err_with(|| format!("Failed to open CSV file: {}", csv_path.display()))?;
+    let mut entries = Vec::new();
+    for result in rdr.deserialize() {
+        let entry: BugEntry = result.wrap_err_with(|| {
+            format!("Failed to deserialize record from {}", csv_path.display())
+        })?;
+        entries.push(entry);
+    }
+    entries.sort_by_key(|e| e.time_taken_millis);
+    Ok(entries)
+}
+
+/// Loads every `*.bugs.csv` in `output_dir`, keyed by run id
+pub fn load_contract_bugs(output_dir: &Path) -> Result<HashMap<String, Vec<BugEntry>>> {
+    let csv_glob_pattern_str = output_dir.join("*.bugs.csv").to_string_lossy().into_owned();
+    let glob_results = glob(&csv_glob_pattern_str).wrap_err_with(|| {
+        format!(
+            "Invalid glob pattern for CSV files: '{}'",
+            csv_glob_pattern_str
+        )
+    })?;
+
+    let mut all_contract_bugs = HashMap::new();
+    for entry_result in glob_results {
+        let csv_path = entry_result.wrap_err("Error accessing file during CSV glob")?;
+        let Some(run_id) = csv_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".bugs.csv"))
+        else {
+            continue;
+        };
+        match read_bugs_from_csv(&csv_path) {
+            Ok(entries) => {
+                all_contract_bugs.insert(run_id.to_string(), entries);
+            }
+            Err(e) => info!(
+                "Error reading or parsing CSV file {}: {:?}",
+                csv_path.display(),
+                e
+            ),
+        }
+    }
+    Ok(all_contract_bugs)
+}
+
+/// Reads `contract_id,final_coverage` lines; blank lines, `#` comments and a
+/// header line are skipped
+fn read_baseline_file(path: &Path) -> Result<HashMap<String, u64>> {
+    let content = fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read baseline file {}", path.display()))?;
+    let mut baseline = HashMap::new();
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((contract_id, coverage)) = line.split_once(',') else {
+            return Err(eyre!(
+                "Malformed line {} in baseline file {}: '{}'",
+                line_number + 1,
+                path.display(),
+                line
+            ));
+        };
+        match coverage.trim().parse::<u64>() {
+            Ok(coverage) => {
+                baseline.insert(contract_id.trim().to_string(), coverage);
+            }
+            Err(_) if line_number == 0 => {} // header
+            Err(e) => {
+                return Err(eyre!(
+                    "Invalid coverage on line {} in baseline file {}: {}",
+                    line_number + 1,
+                    path.display(),
+                    e
+                ));
+            }
+        }
+    }
+    info!(
+        "Read {} baseline values from {}",
+        baseline.len(),
+        path.display()
+    );
+    Ok(baseline)
+}
+
+/// Maximum number of charts drawn on one small-multiples page
+const SMALL_MULTIPLES_PER_PAGE: usize = 36;
+
+/// Draws a grid of branch-coverage-percent charts, one per contract, split
+/// into pages of at most `SMALL_MULTIPLES_PER_PAGE` charts.
+pub fn plot_small_multiples(
+    all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
+    plot_output_dir: &Path,
+    title_prefix: &str,
+    max_points: usize,
+) -> Result<()> {
+    let mut contract_ids: Vec<&String> = all_contract_stats.keys().collect();
+    contract_ids.sort();
+
+    for (page, page_ids) in contract_ids.chunks(SMALL_MULTIPLES_PER_PAGE).enumerate() {
+        let cols = (page_ids.len() as f64).sqrt().ceil() as usize;
+        let rows = page_ids.len().div_ceil(cols);
+        let plot_path =
+            plot_output_dir.join(format!("{}_small_multiples_{}.png", title_prefix, page + 1));
+
+        let temp_plot_path = temp_path(&plot_path);
+        let root_area = BitMapBackend::new(&temp_plot_path, (cols as u32 * 320, rows as u32 * 240))
+            .into_drawing_area();
+        root_area
+            .fill(&WHITE)
+            .wrap_err("Failed to fill plot background")?;
+
+        for (cell, contract_id) in root_area.split_evenly((rows, cols)).iter().zip(page_ids) {
+            let series: Vec<(f64, f64)> = all_contract_stats[*contract_id]
+                .iter()
+                .filter(|e| e.total_branches > 0)
+                .map(|e| {
+                    (
+                        e.time_taken_millis as f64 / 1_000.0,
+                        e.branches_covered as f64 / e.total_branches as f64 * 100.0,
+                    )
+                })
+                .collect();
+            let max_time_seconds = series.iter().map(|(t, _)| *t).fold(0.0_f64, f64::max);
+            let x_axis_max = if max_time_seconds > 0.0 {
+                max_time_seconds * 1.05
+            } else {
+                1.0
+            };
+
+            let mut chart = ChartBuilder::on(cell)
+                .caption(contract_id.as_str(), ("sans-serif", 12).into_font())
+                .margin(5)
+                .x_label_area_size(20)
+                .y_label_area_size(30)
+                .build_cartesian_2d(0.0..x_axis_max, 0.0..100.0)
+                .wrap_err_with(|| format!("Failed to build chart for {}", contract_id))?;
+            chart
+                .configure_mesh()
+                .x_labels(4)
+                .y_labels(5)
+                .label_style(("sans-serif", 10).into_font())
+                .draw()
+                .wrap_err("Failed to draw chart mesh")?;
 
-    for stats_vec in all_contract_stats.values() {
-        for entry in stats_vec {
-            all_timestamps.push(entry.time_taken_millis);
+            if series.is_empty() {
+                info!(
+                    "No branch totals recorded for {}, leaving its chart empty",
+                    contract_id
+                );
+                continue;
+            }
+            chart
+                .draw_series(LineSeries::new(
+                    downsample_lttb(&series, max_points),
+                    series_color(contract_id),
+                ))
+                .wrap_err("Failed to draw data series on chart")?;
         }
+
+        root_area.present().wrap_err("Failed to present chart")?;
+        check_plot_written(&temp_plot_path)?;
+        rename_into_place(&temp_plot_path, &plot_path)?;
+        info!("Small multiples plot saved to {}", plot_path.display());
     }
-    all_timestamps.sort_unstable();
-    all_timestamps.dedup();
 
-    if all_timestamps.is_empty() {
-        info!("No timestamps found in data. Skipping plot.");
-        return Ok(());
+    Ok(())
+}
+
+/// Combines one value per contract into a single aggregate value
+fn combine_contract_values(values: &[u64], mode: AggregateMode) -> f64 {
+    match mode {
+        AggregateMode::Sum => values.iter().sum::<u64>() as f64,
+        AggregateMode::Max => values.iter().copied().max().unwrap_or(0) as f64,
+        AggregateMode::Mean if values.is_empty() => 0.0,
+        AggregateMode::Mean => values.iter().sum::<u64>() as f64 / values.len() as f64,
     }
+}
 
-    let mut total_instructions = 0;
+/// Latest value of every contract while sweeping timestamps in increasing
+/// order. All samples are merged into one time-ordered list once, so a whole
+/// sweep visits each sample a single time instead of rescanning every
+/// contract's series at each timestamp.
+struct LatestValues {
+    /// `(time, contract index, value)`, sorted by time
+    samples: Vec<(u64, usize, f64)>,
+    next: usize,
+    latest: Vec<f64>,
+    sum: f64,
+    max: f64,
+}
 
-    // total instructions in all contracts
-    all_contract_stats.iter().for_each(|(_, stats_vec)| {
-        if let Some(last) =  stats_vec.last(){
-            total_instructions += last.total_instructions;
+impl LatestValues {
+    fn new(contract_stats: &[&Vec<StatsEntry>], value: fn(&StatsEntry) -> f64) -> Self {
+        let mut samples: Vec<(u64, usize, f64)> = contract_stats
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, stats_vec)| {
+                stats_vec
+                    .iter()
+                    .map(move |e| (e.time_taken_millis, idx, value(e)))
+            })
+            .collect();
+        // Stable, so the last of several samples sharing a timestamp wins
+        samples.sort_by_key(|&(time_millis, _, _)| time_millis);
+        Self {
+            samples,
+            next: 0,
+            latest: vec![0.0; contract_stats.len()],
+            sum: 0.0,
+            max: 0.0,
         }
-    });
+    }
 
-    for &ts_nano in &all_timestamps {
-        let mut current_total_instructions = 0;
-        for stats_vec in all_contract_stats.values() {
-            let latest_instr_for_contract = stats_vec
-                .iter()
-                .filter(|e| e.time_taken_millis <= ts_nano)
-                .max_by_key(|e| e.time_taken_millis)
-                .map_or(0, |e| e.instructions_covered);
-            current_total_instructions += latest_instr_for_contract;
+    /// Applies every sample taken at or before `ts_millis`
+    fn advance_to(&mut self, ts_millis: u64) {
+        while let Some(&(time_millis, idx, value)) = self.samples.get(self.next) {
+            if time_millis > ts_millis {
+                break;
+            }
+            let old = std::mem::replace(&mut self.latest[idx], value);
+            self.sum += value - old;
+            if value >= self.max {
+                self.max = value;
+            } else if old == self.max {
+                // Coverage rarely drops, so a rescan here is cheap overall
+                self.max = self.latest.iter().copied().fold(0.0, f64::max);
+            }
+            self.next += 1;
         }
-        aggregated_instructions_over_time.insert(ts_nano, current_total_instructions);
     }
 
-    let plot_data: Vec<(f64, f64)> = aggregated_instructions_over_time
-        .into_iter()
-        .map(|(time_ms, instr_count)| {
-            let time_seconds = time_ms as f64 / 1_000.0;
-            let instructions_k = instr_count as f64 / 1000.0;
-            (time_seconds, instructions_k)
-        })
-        .collect();
+    fn mean(&self) -> f64 {
+        if self.latest.is_empty() {
+            0.0
+        } else {
+            self.sum / self.latest.len() as f64
+        }
+    }
+}
 
-    if plot_data.is_empty() {
-        info!("Aggregated plot data is empty. Skipping plot generation.");
-        return Ok(());
+/// Aligns the runs and combines repetitions as set in `options`, giving the
+/// per-contract stats the aggregate is computed from
+fn prepare_stats(
+    all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
+    options: &PlotOptions,
+) -> HashMap<String, Vec<StatsEntry>> {
+    let by_execs;
+    let all_contract_stats = if options.x_axis == XAxis::Execs {
+        by_execs = index_by_execs(all_contract_stats);
+        &by_execs
+    } else {
+        all_contract_stats
+    };
+    // Align before combining so that repetitions line up with each other too
+    if options.align == Align::FirstSample {
+        let mut aligned = all_contract_stats.clone();
+        align_to_first_sample(&mut aligned);
+        combine_repetitions(&aligned, options.aggregate)
+    } else {
+        combine_repetitions(all_contract_stats, options.aggregate)
     }
+}
 
-    // store the overall csv stats
-    let total_instructions_k = (total_instructions as f64) / 1000.0;
-    store_overall_stats_csv(plot_output_dir, &title_prefix, total_instructions_k, &plot_data)?;
-    store_simplified_stats_csv(plot_output_dir, &title_prefix, total_instructions_k, &plot_data)?;
+/// Puts each sample's execs count in place of its time, so that the plots
+/// and overall CSVs show thousands of executions where they show seconds.
+/// Samples without an execs count, and runs without any, are left out.
+fn index_by_execs(
+    all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
+) -> HashMap<String, Vec<StatsEntry>> {
+    let mut by_execs = HashMap::new();
+    for (run_id, entries) in all_contract_stats {
+        let mut entries: Vec<StatsEntry> = entries
+            .iter()
+            .filter_map(|entry| {
+                entry.execs.map(|execs| StatsEntry {
+                    time_taken_millis: execs,
+                    ..entry.clone()
+                })
+            })
+            .collect();
+        if entries.is_empty() {
+            warn!(
+                "{} has no samples with an execs count, leaving it out of the plot",
+                run_id
+            );
+            continue;
+        }
+        entries.sort_by_key(|entry| entry.time_taken_millis);
+        by_execs.insert(run_id.clone(), entries);
+    }
+    by_execs
+}
+
+/// Sorted, distinct sample times of all contracts
+fn collect_timestamps(all_contract_stats: &HashMap<String, Vec<StatsEntry>>) -> Vec<u64> {
+    let mut all_timestamps: Vec<u64> = all_contract_stats
+        .values()
+        .flat_map(|stats_vec| stats_vec.iter().map(|entry| entry.time_taken_millis))
+        .collect();
+    all_timestamps.sort_unstable();
+    all_timestamps.dedup();
+    all_timestamps
+}
 
-    let plot_path = plot_output_dir.join(format!("{}_overall_instructions_plot.png", title_prefix));
+/// Overall covered instructions (thousands) against time (seconds) at every
+/// sample time of any contract, the data of the overall stats CSV and plot
+pub fn aggregate(
+    all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
+    mode: AggregateMode,
+) -> Vec<(f64, f64)> {
+    let all_stats: Vec<&Vec<StatsEntry>> = all_contract_stats.values().collect();
+    aggregate_instructions(&all_stats, &collect_timestamps(all_contract_stats), mode)
+}
 
-    let root_area = BitMapBackend::new(&plot_path, (1024, 768)).into_drawing_area();
-    root_area
-        .fill(&WHITE)
-        .wrap_err("Failed to fill plot background")?;
+/// Combines the latest instructions covered by each contract at every
+/// timestamp according to `mode`, as `(seconds, thousands of instructions)`.
+/// `timestamps` must be sorted.
+fn aggregate_instructions(
+    contract_stats: &[&Vec<StatsEntry>],
+    timestamps: &[u64],
+    mode: AggregateMode,
+) -> Vec<(f64, f64)> {
+    let mut latest = LatestValues::new(contract_stats, |e| e.instructions_covered as f64);
+    timestamps
+        .iter()
+        .map(|&ts_millis| {
+            latest.advance_to(ts_millis);
+            let instructions = match mode {
+                AggregateMode::Sum => latest.sum,
+                AggregateMode::Max => latest.max,
+                AggregateMode::Mean => latest.mean(),
+            };
+            (ts_millis as f64 / 1_000.0, instructions / 1000.0)
+        })
+        .collect()
+}
 
-    let max_time_seconds = plot_data.iter().map(|(t, _)| *t).fold(0.0_f64, f64::max) * 1.1;
-    let max_instr_k = plot_data.iter().map(|(_, i)| *i).fold(0.0_f64, f64::max) * 1.1;
+/// Reference for `aggregate`: the O(T×C×N) scan it replaced, which searches
+/// all samples of every contract at each timestamp. Used by the equivalence
+/// test and the `aggregate` benchmark.
+#[doc(hidden)]
+pub fn naive_aggregate(
+    all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
+    mode: AggregateMode,
+) -> Vec<(f64, f64)> {
+    collect_timestamps(all_contract_stats)
+        .iter()
+        .map(|&ts_millis| {
+            let latest: Vec<u64> = all_contract_stats
+                .values()
+                .map(|entries| {
+                    entries
+                        .iter()
+                        .filter(|e| e.time_taken_millis <= ts_millis)
+                        .max_by_key(|e| e.time_taken_millis)
+                        .map_or(0, |e| e.instructions_covered)
+                })
+                .collect();
+            (
+                ts_millis as f64 / 1_000.0,
+                combine_contract_values(&latest, mode) / 1000.0,
+            )
+        })
+        .collect()
+}
 
-    let x_axis_max = if max_time_seconds > 0.0 {
-        max_time_seconds
-    } else {
-        1.0
-    };
-    // Add 10% padding above the total instructions line
-    let y_axis_max = if max_instr_k > 0.0 {
-        f64::max(max_instr_k, total_instructions_k) * 1.1
-    } else {
-        1.0
+/// `contracts` series of `samples` growing coverage stats each, at
+/// pseudo-random times that differ between contracts. Steps of 0 give several
+/// samples at one timestamp. Test and benchmark data for `aggregate`.
+#[doc(hidden)]
+pub fn generate_stats(contracts: usize, samples: usize) -> HashMap<String, Vec<StatsEntry>> {
+    let mut state: u64 = 42;
+    let mut next = move || {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        state >> 33
     };
+    (0..contracts)
+        .map(|contract| {
+            let mut time_taken_millis = 0;
+            let mut instructions_covered = 0;
+            let entries = (0..samples)
+                .map(|_| {
+                    time_taken_millis += next() % 300;
+                    instructions_covered += next() % 20;
+                    StatsEntry {
+                        instructions_covered,
+                        branches_covered: instructions_covered / 4,
+                        total_instructions: 10_000,
+                        time_taken_millis,
+                        total_branches: 2_500,
+                        coverage_percent: None,
+                        abs_timestamp_millis: None,
+                        execs: None,
+                    }
+                })
+                .collect();
+            (format!("c{}", contract), entries)
+        })
+        .collect()
+}
 
-    let mut chart = ChartBuilder::on(&root_area)
-        .caption(
-            format!("{} Overall Instructions Covered vs. Time", title_prefix),
-            ("sans-serif", 30).into_font(),
-        )
-        .margin(10)
-        .x_label_area_size(40)
-        .y_label_area_size(50)
-        .build_cartesian_2d(0.0..x_axis_max, 0.0..y_axis_max)
-        .wrap_err("Failed to build chart")?;
+/// Combines the latest reported coverage percentage of each contract at every
+/// timestamp. Percentages cannot be summed, so `Sum` averages them like `Mean`.
+fn aggregate_percent(
+    contract_stats: &[&Vec<StatsEntry>],
+    timestamps: &[u64],
+    mode: AggregateMode,
+) -> Vec<(f64, f64)> {
+    let mut latest = LatestValues::new(contract_stats, |e| e.instruction_percent().unwrap_or(0.0));
+    timestamps
+        .iter()
+        .map(|&ts_millis| {
+            latest.advance_to(ts_millis);
+            let percent = match mode {
+                AggregateMode::Max => latest.max,
+                AggregateMode::Sum | AggregateMode::Mean => latest.mean(),
+            };
+            (ts_millis as f64 / 1_000.0, percent)
+        })
+        .collect()
+}
 
-    chart
-        .configure_mesh()
-        .x_desc("Time (seconds)")
-        .y_desc("Number of Instructions / 10^3")
-        .draw()
-        .wrap_err("Failed to draw chart mesh")?;
+/// Sibling of `path` that an artifact is written to before `rename_into_place`
+/// moves it over `path`, so readers never see a partially written file. The
+/// extension is kept since plotters picks the image format from it.
+pub fn temp_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match path.extension() {
+        Some(ext) => path.with_file_name(format!("{}.tmp.{}", stem, ext.to_string_lossy())),
+        None => path.with_file_name(format!("{}.tmp", stem)),
+    }
+}
 
-    // Draw the coverage progress line
-    let coverage_line = chart
-        .draw_series(LineSeries::new(plot_data.clone(), &RED))
-        .wrap_err("Failed to draw data series on chart")?
-        .legend(move |(x,y)| PathElement::new(vec![(x, y), (x + 20, y)], &RED));
+/// The `run_manifest.json` of `dir`, if there is a readable one
+fn read_manifest(dir: &Path) -> Option<RunManifest> {
+    let manifest = fs::read_to_string(dir.join("run_manifest.json")).ok()?;
+    serde_json::from_str(&manifest).ok()
+}
 
-    // Draw horizontal line for total instructions
-    let total_line = chart
-        .draw_series(DashedLineSeries::new(
-            vec![(0.0, total_instructions_k), (x_axis_max, total_instructions_k)],
-            5,
-            10,
-            ShapeStyle {
-                color: BLACK.mix(1.0),
-                filled: false,
-                stroke_width: 1,
-            },
-        ))?
-        .legend(move |(x,y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLUE.mix(0.5)));
+/// Writes the fuzzer version in the bottom right corner of the plot
+fn draw_fuzzer_version(
+    root_area: &DrawingArea<BitMapBackend<'_>, plotters::coord::Shift>,
+    options: &PlotOptions,
+) -> Result<()> {
+    let Some(version) = &options.fuzzer_version else {
+        return Ok(());
+    };
+    let (width, height) = root_area.dim_in_pixel();
+    let style = ("sans-serif", 12)
+        .into_font()
+        .color(&BLACK.mix(0.6))
+        .pos(Pos::new(HPos::Right, VPos::Bottom));
+    root_area
+        .draw(&Text::new(
+            format!("fuzzer: {}", version),
+            (width as i32 - 4, height as i32 - 2),
+            style,
+        ))
+        .wrap_err("Failed to draw the fuzzer version")?;
+    Ok(())
+}
 
-    // Configure the legend
-    chart
-        .configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
-        .draw()?;
+/// Fails if `plot_path` is missing or empty after `present()`. Plotters can
+/// write a 0-byte PNG without an error when no font could be loaded.
+fn check_plot_written(plot_path: &Path) -> Result<()> {
+    let len = fs::metadata(plot_path).map_or(0, |m| m.len());
+    if len == 0 {
+        return Err(eyre!(
+            "Plot {} was not written or is empty; this usually means no font could be loaded. \
+             Install a system font (e.g. fonts-dejavu-core and fontconfig) or build plotters \
+             with the `ab_glyph` feature",
+            plot_path.display()
+        ));
+    }
+    Ok(())
+}
 
-    root_area.present().wrap_err("Failed to present chart")?;
-    info!("Plot saved to {}", plot_path.display());
+pub fn rename_into_place(temp_path: &Path, path: &Path) -> Result<()> {
+    fs::rename(temp_path, path).wrap_err_with(|| {
+        format!(
+            "Failed to move {} to {}",
+            temp_path.display(),
+            path.display()
+        )
+    })
+}
 
-    Ok(())
+/// Start of the overall plot and CSV file names; plots against executions
+/// get their own files so they do not replace the ones against time
+fn overall_file_prefix(title_prefix: &str, x_axis: XAxis) -> Cow<'_, str> {
+    match x_axis {
+        XAxis::Time => Cow::Borrowed(title_prefix),
+        XAxis::Execs => Cow::Owned(format!("{}_execs", title_prefix)),
+    }
 }
 
-fn store_overall_stats_csv(plot_output_dir: &Path, title_prefix: &str, total_instructions_k: f64, plot_data: &[(f64, f64)]) -> Result<()> {
-    let overall_stats_csv_path =
-        plot_output_dir.join(format!("{}_overall_instructions_stats.csv", title_prefix));
-    let mut wtr = csv::Writer::from_path(&overall_stats_csv_path).wrap_err_with(|| {
+/// Creates an overall stats CSV, starting with `#` comment lines that describe
+/// where the numbers come from. Readers skip them with `ReaderBuilder::comment`.
+/// The CSV is written to a temporary file until `finish_overall_csv`.
+fn create_overall_csv(
+    csv_path: &Path,
+    title_prefix: &str,
+    x_axis: XAxis,
+    num_contracts: usize,
+) -> Result<csv::Writer<fs::File>> {
+    let temp_csv_path = temp_path(csv_path);
+    let mut file = fs::File::create(&temp_csv_path).wrap_err_with(|| {
         format!(
             "Failed to create CSV writer for {}",
-            overall_stats_csv_path.display()
+            temp_csv_path.display()
         )
     })?;
-    wtr.write_record(["time_seconds", "instructions(k)", "total_instructions(k)"])
-        .wrap_err("Failed to write CSV header")?;
+    let x_unit = match x_axis {
+        XAxis::Time => "time_unit: seconds",
+        XAxis::Execs => "x_unit: thousand executions",
+    };
+    writeln!(
+        file,
+        "# title: {}\n# generated_at: {}\n# contracts: {}\n# {}",
+        title_prefix,
+        chrono::Utc::now().to_rfc3339(),
+        num_contracts,
+        x_unit
+    )
+    .wrap_err_with(|| format!("Failed to write metadata to {}", csv_path.display()))?;
+    Ok(csv::Writer::from_writer(file))
+}
 
+/// Flushes a CSV from `create_overall_csv` and moves it to `csv_path`
+fn finish_overall_csv(mut wtr: csv::Writer<fs::File>, csv_path: &Path) -> Result<()> {
+    wtr.flush().wrap_err("Failed to flush CSV writer")?;
+    drop(wtr);
+    rename_into_place(&temp_path(csv_path), csv_path)
+}
+
+fn store_overall_stats_csv(
+    plot_output_dir: &Path,
+    title_prefix: &str,
+    x_axis: XAxis,
+    num_contracts: usize,
+    total_instructions_k: f64,
+    plot_data: &[(f64, f64)],
+) -> Result<()> {
+    let overall_stats_csv_path = plot_output_dir.join(format!(
+        "{}_overall_instructions_stats.csv",
+        overall_file_prefix(title_prefix, x_axis)
+    ));
+    let mut wtr = create_overall_csv(&overall_stats_csv_path, title_prefix, x_axis, num_contracts)?;
+    wtr.write_record([
+        x_axis.csv_column(),
+        "instructions(k)",
+        "total_instructions(k)",
+    ])
+    .wrap_err("Failed to write CSV header")?;
 
     let total_instructions_k_str = total_instructions_k.to_string();
     for (time_seconds, instructions_k) in plot_data {
-        wtr.write_record([time_seconds.to_string(), instructions_k.to_string(), total_instructions_k_str.clone()])
+        wtr.write_record([
+            time_seconds.to_string(),
+            instructions_k.to_string(),
+            total_instructions_k_str.clone(),
+        ])
+        .wrap_err("Failed to write CSV record")?;
+    }
+
+    finish_overall_csv(wtr, &overall_stats_csv_path)?;
+    Ok(())
+}
+
+/// Writes one column per contract with its latest covered instructions at
+/// every timestamp, the values `aggregate_instructions` combines
+fn store_aggregate_debug_csv(
+    plot_output_dir: &Path,
+    title_prefix: &str,
+    x_axis: XAxis,
+    all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
+    timestamps: &[u64],
+) -> Result<()> {
+    let debug_csv_path = plot_output_dir.join(format!(
+        "{}_aggregate_debug.csv",
+        overall_file_prefix(title_prefix, x_axis)
+    ));
+    let mut wtr = create_overall_csv(
+        &debug_csv_path,
+        title_prefix,
+        x_axis,
+        all_contract_stats.len(),
+    )?;
+
+    let mut contract_ids: Vec<&String> = all_contract_stats.keys().collect();
+    contract_ids.sort();
+    let mut header = vec![x_axis.csv_column()];
+    header.extend(contract_ids.iter().map(|id| id.as_str()));
+    wtr.write_record(&header)
+        .wrap_err("Failed to write CSV header")?;
+
+    let contract_stats: Vec<&Vec<StatsEntry>> = contract_ids
+        .iter()
+        .map(|contract_id| &all_contract_stats[*contract_id])
+        .collect();
+    let mut latest = LatestValues::new(&contract_stats, |e| e.instructions_covered as f64);
+    for &ts_millis in timestamps {
+        latest.advance_to(ts_millis);
+        let mut record = vec![(ts_millis as f64 / 1_000.0).to_string()];
+        record.extend(latest.latest.iter().map(|value| value.to_string()));
+        wtr.write_record(&record)
             .wrap_err("Failed to write CSV record")?;
     }
 
-    wtr.flush().wrap_err("Failed to flush CSV writer")?;
+    finish_overall_csv(wtr, &debug_csv_path)?;
+    info!("Aggregate debug CSV saved to {}", debug_csv_path.display());
     Ok(())
 }
 
-fn store_simplified_stats_csv(plot_output_dir: &Path, title_prefix: &str, total_instructions_k: f64, plot_data: &[(f64, f64)]) -> Result<()> {
-    let overall_stats_csv_path =
-        plot_output_dir.join(format!("{}_overall_instructions_stats_simplified.csv", title_prefix));
-    let mut wtr = csv::Writer::from_path(&overall_stats_csv_path).wrap_err_with(|| {
-        format!(
-            "Failed to create CSV writer for {}",
-            overall_stats_csv_path.display()
-        )
-    })?;
+fn store_simplified_stats_csv(
+    plot_output_dir: &Path,
+    title_prefix: &str,
+    num_contracts: usize,
+    total_instructions_k: f64,
+    plot_data: &[(f64, f64)],
+) -> Result<()> {
+    let overall_stats_csv_path = plot_output_dir.join(format!(
+        "{}_overall_instructions_stats_simplified.csv",
+        title_prefix
+    ));
+    let mut wtr = create_overall_csv(
+        &overall_stats_csv_path,
+        title_prefix,
+        XAxis::Time,
+        num_contracts,
+    )?;
     wtr.write_record(["time_seconds", "instructions(k)", "total_instructions(k)"])
         .wrap_err("Failed to write CSV header")?;
 
     let total_instructions_k_str = total_instructions_k.to_string();
-    let time_steps= [1.0, 5.0, 10.0, 30.0];
-    let data:&Vec<(f64, f64)>  = &time_steps.iter().map(|t|{
-        let instr = plot_data.iter().filter(|(x, _)| *x<=*t).next_back().unwrap().1;
-        (*t, instr)
-    }).collect();
+    let time_steps = [1.0, 5.0, 10.0, 30.0];
+    let data: &Vec<(f64, f64)> = &time_steps
+        .iter()
+        .map(|t| {
+            // Nothing is covered before the first sample, which is after
+            // t=0 with `--exclude-zero-time`
+            let instr = plot_data
+                .iter()
+                .filter(|(x, _)| *x <= *t)
+                .next_back()
+                .map_or(0.0, |&(_, instr)| instr);
+            (*t, instr)
+        })
+        .collect();
 
     for (time_seconds, instructions_k) in data {
-        wtr.write_record([time_seconds.to_string(), instructions_k.to_string(), total_instructions_k_str.clone()])
-            .wrap_err("Failed to write CSV record")?;
+        wtr.write_record([
+            time_seconds.to_string(),
+            instructions_k.to_string(),
+            total_instructions_k_str.clone(),
+        ])
+        .wrap_err("Failed to write CSV record")?;
     }
 
-    wtr.flush().wrap_err("Failed to flush CSV writer")?;
+    finish_overall_csv(wtr, &overall_stats_csv_path)?;
     Ok(())
 }
 
-pub fn handle_plot_command(args: PlotArgs) -> Result<()> {
-    if !args.output_dir.exists() {
-        return Err(eyre!(
-            "Output directory {} does not exist. Cannot read CSV data.",
-            args.output_dir.display()
-        ));
-    }
-    if !args.output_dir.is_dir() {
-        return Err(eyre!(
-            "Path {} is not a directory.",
-            args.output_dir.display()
-        ));
+/// Reads a CSV on a worker thread, giving up after `timeout` so that a stale
+/// network mount cannot hang the caller. The worker is left to finish on its own.
+fn read_stats_with_timeout(csv_path: &Path, timeout: Option<Duration>) -> Result<Vec<StatsEntry>> {
+    let Some(timeout) = timeout else {
+        return read_stats_from_csv(csv_path);
+    };
+    let (tx, rx) = mpsc::channel();
+    let worker_path = csv_path.to_path_buf();
+    thread::spawn(move || {
+        let _ = tx.send(read_stats_from_csv(&worker_path));
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(_) => Err(eyre!(
+            "Skipped {}: reading did not finish within {}s",
+            csv_path.display(),
+            timeout.as_secs()
+        )),
     }
+}
 
+/// Loads every `*.instructions.stats.csv` in `output_dir`. With `read_timeout`,
+/// a CSV that takes longer to read is skipped.
+pub fn load_contract_stats(
+    output_dir: &Path,
+    read_timeout: Option<Duration>,
+) -> Result<HashMap<String, Vec<StatsEntry>>> {
     let mut all_contract_stats: HashMap<String, Vec<StatsEntry>> = HashMap::new();
-    let csv_glob_pattern_str = args
-        .output_dir
+    let csv_glob_pattern_str = output_dir
         .join("*.instructions.stats.csv")
         .to_string_lossy()
         .into_owned();
@@ -269,7 +1952,7 @@ pub fn handle_plot_command(args: PlotArgs) -> Result<()> {
                         contract_id,
                         csv_path.display()
                     );
-                    match read_stats_from_csv(&csv_path) {
+                    match read_stats_with_timeout(&csv_path, read_timeout) {
                         Ok(entries) => {
                             if entries.is_empty() {
                                 info!(
@@ -316,25 +1999,442 @@ pub fn handle_plot_command(args: PlotArgs) -> Result<()> {
         );
     }
 
+    Ok(all_contract_stats)
+}
+
+/// Directory an archived output directory was created from: `<dir>.tar.gz`
+/// or `<dir>.tgz` yields `<dir>`
+fn archived_output_dir(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_str()?;
+    let dir_name = file_name
+        .strip_suffix(".tar.gz")
+        .or_else(|| file_name.strip_suffix(".tgz"))?;
+    Some(path.with_file_name(dir_name))
+}
+
+/// Extracts the per-contract CSVs of an archived output directory into a
+/// temporary directory, removed when the returned `TempDir` is dropped. Entries
+/// are flattened to their file names.
+fn extract_archived_csvs(archive_path: &Path) -> Result<TempDir> {
+    let temp_dir = TempDir::new().wrap_err("Failed to create temporary directory")?;
+    let file = fs::File::open(archive_path)
+        .wrap_err_with(|| format!("Failed to open archive {}", archive_path.display()))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let mut extracted = 0;
+    for entry in archive
+        .entries()
+        .wrap_err_with(|| format!("Failed to read archive {}", archive_path.display()))?
+    {
+        let mut entry = entry
+            .wrap_err_with(|| format!("Failed to read entry of {}", archive_path.display()))?;
+        let path = entry
+            .path()
+            .wrap_err_with(|| format!("Invalid entry path in {}", archive_path.display()))?
+            .into_owned();
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if !entry.header().entry_type().is_file()
+            || !(file_name.ends_with(".instructions.stats.csv") || file_name.ends_with(".bugs.csv"))
+        {
+            continue;
+        }
+        let target = temp_dir.path().join(file_name);
+        entry.unpack(&target).wrap_err_with(|| {
+            format!(
+                "Failed to extract {} from {}",
+                path.display(),
+                archive_path.display()
+            )
+        })?;
+        extracted += 1;
+    }
+    info!(
+        "Extracted {} CSV files from {} to {}",
+        extracted,
+        archive_path.display(),
+        temp_dir.path().display()
+    );
+    Ok(temp_dir)
+}
+
+pub fn handle_plot_command(args: PlotArgs) -> Result<()> {
+    plot_once(&args)?;
+    let Some(interval) = args.follow else {
+        return Ok(());
+    };
+    info!(
+        "Following {}, re-plotting every {}s",
+        args.output_dir.display(),
+        interval
+    );
+    loop {
+        thread::sleep(Duration::from_secs(interval));
+        // CSVs may be rewritten while they are read, the next pass retries
+        if let Err(e) = plot_once(&args) {
+            warn!("Failed to re-plot {}: {:?}", args.output_dir.display(), e);
+        }
+    }
+}
+
+/// Reads the CSVs of `args.output_dir` once and writes its plots
+fn plot_once(args: &PlotArgs) -> Result<()> {
+    if !args.output_dir.exists() {
+        return Err(eyre!(
+            "Output directory {} does not exist. Cannot read CSV data.",
+            args.output_dir.display()
+        ));
+    }
+    // An archived output directory is read from a temporary copy of its CSVs
+    // and plotted into the directory it was archived from
+    let (output_dir, extracted) = if args.output_dir.is_dir() {
+        (args.output_dir.clone(), None)
+    } else if let Some(output_dir) = archived_output_dir(&args.output_dir) {
+        (output_dir, Some(extract_archived_csvs(&args.output_dir)?))
+    } else {
+        return Err(eyre!(
+            "Output path {} exists and is a file, not a directory",
+            args.output_dir.display()
+        ));
+    };
+    let data_dir = extracted
+        .as_ref()
+        .map_or(output_dir.as_path(), |temp_dir| temp_dir.path());
+
+    let all_contract_stats = match &args.sqlite {
+        Some(db_path) => load_stats_from_sqlite(db_path)?,
+        None => {
+            let read_timeout = args.read_timeout_seconds.map(Duration::from_secs);
+            load_contract_stats(data_dir, read_timeout)?
+        }
+    };
+
     if all_contract_stats.is_empty() {
         info!("No data loaded from CSV files. Cannot generate aggregate plot.");
         return Ok(());
     }
+    if let Some(prometheus_path) = &args.prometheus {
+        write_prometheus_metrics(prometheus_path, &all_contract_stats)?;
+    }
+
+    let num_sparse = all_contract_stats
+        .values()
+        .filter(|entries| entries.len() < args.min_samples)
+        .count();
+    let plotted_stats = if num_sparse == 0 {
+        Cow::Borrowed(&all_contract_stats)
+    } else {
+        info!(
+            "Leaving {} contracts with fewer than {} samples out of the plot",
+            num_sparse, args.min_samples
+        );
+        Cow::Owned(
+            all_contract_stats
+                .iter()
+                .filter(|(_, entries)| entries.len() >= args.min_samples)
+                .map(|(contract_id, entries)| (contract_id.clone(), entries.clone()))
+                .collect(),
+        )
+    };
 
-    // The plot will be saved in args.output_dir
+    // The plot will be saved in output_dir
     // Ensure the directory exists for writing the plot (it should, as we checked earlier for reading)
-    fs::create_dir_all(&args.output_dir).wrap_err_with(|| {
+    fs::create_dir_all(&output_dir).wrap_err_with(|| {
         format!(
             "Failed to ensure output directory for plot exists: {}",
-            args.output_dir.display()
+            output_dir.display()
         )
     })?;
 
-    aggregate_and_plot_data(&all_contract_stats, &args.output_dir, None)?;
+    if args.small_multiples {
+        let title_prefix = args.plot_title.clone().unwrap_or_else(|| {
+            output_dir
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned()
+        });
+        plot_small_multiples(&plotted_stats, &output_dir, &title_prefix, args.max_points)?;
+        if args.html_report {
+            write_html_report(&output_dir, args.plot_title.as_deref(), &all_contract_stats)?;
+        }
+        info!(
+            "Plot command complete. Plots are in the '{}' directory.",
+            output_dir.display()
+        );
+        return Ok(());
+    }
+
+    let baseline = args
+        .baseline
+        .as_deref()
+        .map(read_baseline_file)
+        .transpose()?;
+    let manifest = read_manifest(data_dir).or_else(|| read_manifest(&output_dir));
+    let timeout_seconds = match (&manifest, args.show_timeout) {
+        (Some(manifest), true) => Some(manifest.fuzz_timeout_seconds),
+        (None, true) => {
+            warn!(
+                "No run_manifest.json in {}, not drawing the timeout line",
+                output_dir.display()
+            );
+            None
+        }
+        (_, false) => None,
+    };
+    let options = PlotOptions {
+        max_points: args.max_points,
+        metric: args.metric,
+        smooth: args.smooth,
+        y_unit: args.y_unit,
+        aggregate: args.aggregate,
+        aggregate_mode: args.aggregate_mode,
+        baseline,
+        align: args.align,
+        debug_aggregate: args.debug_aggregate,
+        csv_only: args.csv_only,
+        plot_kind: args.plot_kind,
+        line_width: args.line_width,
+        markers: args.markers,
+        max_bands: args.max_bands as usize,
+        fuzzer_version: manifest
+            .as_ref()
+            .and_then(|manifest| manifest.fuzzer_version.clone()),
+        timeout_seconds,
+        x_axis: args.x_axis,
+        caption: args.caption.clone(),
+        x_label: args.x_label.clone(),
+        y_label: args.y_label.clone(),
+    };
+    if options.metric == Metric::Bugs {
+        // Read from `data_dir`, which differs from `output_dir` for archives
+        let all_contract_bugs = load_contract_bugs(data_dir)?;
+        plot_bugs(
+            &all_contract_bugs,
+            &output_dir,
+            args.plot_title.clone(),
+            &options,
+        )?;
+    } else {
+        aggregate_and_plot_data(
+            &plotted_stats,
+            &output_dir,
+            args.plot_title.clone(),
+            &options,
+        )?;
+    }
+    if args.html_report {
+        write_html_report(&output_dir, args.plot_title.as_deref(), &all_contract_stats)?;
+    }
     info!(
         "Plot command complete. Plot is in the '{}' directory.",
-        args.output_dir.display()
+        output_dir.display()
     );
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_csv(content: &str) -> Vec<StatsEntry> {
+        let dir = tempfile::tempdir().unwrap();
+        let csv_path = dir.path().join("c1.instructions.stats.csv");
+        fs::write(&csv_path, content).unwrap();
+        read_stats_from_csv(&csv_path).unwrap()
+    }
+
+    #[test]
+    fn read_stats_from_csv_reads_empty_file() {
+        assert!(read_csv("").is_empty());
+    }
+
+    #[test]
+    fn read_stats_from_csv_reads_header_only_file() {
+        assert!(
+            read_csv(
+                "instructions_covered,branches_covered,total_instructions,time_taken_millis,total_branches,coverage_percent,abs_timestamp_millis,execs\n"
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn read_stats_from_csv_reads_old_three_column_csv() {
+        let entries = read_csv(
+            "instructions_covered,branches_covered,time_taken_millis\n9,2,1000\n5,1,500\n",
+        );
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            (
+                entries[0].instructions_covered,
+                entries[0].branches_covered,
+                entries[0].time_taken_millis
+            ),
+            (5, 1, 500)
+        );
+        assert_eq!(entries[1].instructions_covered, 9);
+        assert_eq!(entries[1].total_instructions, 0);
+        assert_eq!(entries[1].total_branches, 0);
+        assert_eq!(entries[1].coverage_percent, None);
+        assert_eq!(entries[1].execs, None);
+    }
+
+    fn entry(time_taken_millis: u64, instructions_covered: u64) -> StatsEntry {
+        StatsEntry {
+            instructions_covered,
+            branches_covered: 0,
+            total_instructions: 10_000,
+            time_taken_millis,
+            total_branches: 0,
+            coverage_percent: None,
+            abs_timestamp_millis: None,
+            execs: None,
+        }
+    }
+
+    fn stats(runs: &[(&str, Vec<StatsEntry>)]) -> HashMap<String, Vec<StatsEntry>> {
+        runs.iter()
+            .map(|(run_id, entries)| (run_id.to_string(), entries.clone()))
+            .collect()
+    }
+
+    fn covered(entries: &[StatsEntry]) -> Vec<(u64, u64)> {
+        entries
+            .iter()
+            .map(|e| (e.time_taken_millis, e.instructions_covered))
+            .collect()
+    }
+
+    #[test]
+    fn aggregate_combines_latest_coverage_of_each_contract() {
+        let all_contract_stats = stats(&[
+            ("c1", vec![entry(1000, 1000), entry(3000, 3000)]),
+            ("c2", vec![entry(2000, 2000)]),
+        ]);
+        assert_eq!(
+            aggregate(&all_contract_stats, AggregateMode::Sum),
+            vec![(1.0, 1.0), (2.0, 3.0), (3.0, 5.0)]
+        );
+        assert_eq!(
+            aggregate(&all_contract_stats, AggregateMode::Max),
+            vec![(1.0, 1.0), (2.0, 2.0), (3.0, 3.0)]
+        );
+        assert_eq!(
+            aggregate(&all_contract_stats, AggregateMode::Mean),
+            vec![(1.0, 0.5), (2.0, 1.5), (3.0, 2.5)]
+        );
+    }
+
+    #[test]
+    fn aggregate_of_no_contracts_is_empty() {
+        assert!(aggregate(&HashMap::new(), AggregateMode::Sum).is_empty());
+    }
+
+    #[test]
+    fn prepare_stats_combines_repetitions() {
+        let all_contract_stats = stats(&[
+            ("c1.run1", vec![entry(0, 10), entry(1000, 20)]),
+            ("c1.run2", vec![entry(0, 30), entry(1000, 30)]),
+            ("c2", vec![entry(1000, 5)]),
+        ]);
+        let options = PlotOptions {
+            aggregate: Aggregate::Best,
+            ..PlotOptions::default()
+        };
+        let prepared = prepare_stats(&all_contract_stats, &options);
+        assert_eq!(prepared.len(), 2);
+        assert_eq!(covered(&prepared["c1"]), vec![(0, 30), (1000, 30)]);
+        assert_eq!(covered(&prepared["c2"]), vec![(1000, 5)]);
+    }
+
+    #[test]
+    fn prepare_stats_aligns_to_first_sample() {
+        let all_contract_stats = stats(&[("c1", vec![entry(500, 1), entry(1500, 2)])]);
+        let options = PlotOptions {
+            align: Align::FirstSample,
+            ..PlotOptions::default()
+        };
+        let prepared = prepare_stats(&all_contract_stats, &options);
+        assert_eq!(covered(&prepared["c1"]), vec![(0, 1), (1000, 2)]);
+        // The input is left untouched
+        assert_eq!(
+            covered(&all_contract_stats["c1"]),
+            vec![(500, 1), (1500, 2)]
+        );
+    }
+
+    #[test]
+    fn prepare_stats_indexes_by_execs() {
+        let with_execs = |time_taken_millis, instructions_covered, execs| StatsEntry {
+            execs,
+            ..entry(time_taken_millis, instructions_covered)
+        };
+        let all_contract_stats = stats(&[
+            (
+                "c1",
+                vec![
+                    with_execs(100, 1, Some(5000)),
+                    with_execs(200, 2, None),
+                    with_execs(300, 3, Some(9000)),
+                ],
+            ),
+            ("c2", vec![entry(100, 1)]),
+        ]);
+        let options = PlotOptions {
+            x_axis: XAxis::Execs,
+            ..PlotOptions::default()
+        };
+        let prepared = prepare_stats(&all_contract_stats, &options);
+        assert_eq!(prepared.len(), 1);
+        assert_eq!(covered(&prepared["c1"]), vec![(5000, 1), (9000, 3)]);
+    }
+
+    #[test]
+    fn aggregate_sweep_matches_naive_scan() {
+        let all_contract_stats = generate_stats(8, 50);
+        for mode in [AggregateMode::Sum, AggregateMode::Max, AggregateMode::Mean] {
+            assert_eq!(
+                aggregate(&all_contract_stats, mode),
+                naive_aggregate(&all_contract_stats, mode),
+                "{:?}",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn simplified_stats_csv_starts_at_zero_without_a_zero_time_sample() {
+        let log = "INFO Ityfuzz start at 1000
+INFO Coverage stat: time-millis: 1000 instructions: 1/100 branches: 1/10
+INFO Coverage stat: time-millis: 3500 instructions: 2000/10000 branches: 1/10
+";
+        let options = crate::run::ParseOptions {
+            exclude_zero_time: true,
+            ..Default::default()
+        };
+        let entries = crate::run::parse_log(log, "c1", &options, &mut Vec::new()).unwrap();
+        assert_eq!(covered(&entries), vec![(2500, 2000)]);
+
+        let plot_data = aggregate(&stats(&[("c1", entries)]), AggregateMode::Sum);
+        let dir = tempfile::tempdir().unwrap();
+        store_simplified_stats_csv(dir.path(), "b1", 1, 10.0, &plot_data).unwrap();
+        let csv = fs::read_to_string(
+            dir.path()
+                .join("b1_overall_instructions_stats_simplified.csv"),
+        )
+        .unwrap();
+        let rows: Vec<&str> = csv.lines().filter(|line| !line.starts_with('#')).collect();
+        assert_eq!(
+            rows,
+            vec![
+                "time_seconds,instructions(k),total_instructions(k)",
+                "1,0,10",
+                "5,2,10",
+                "10,2,10",
+                "30,2,10",
+            ]
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::plot::split_run_id;
+use crate::types::StatsEntry;
+use eyre::{Result, WrapErr};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Gauge written per contract run, read from its last sample
+struct Gauge {
+    name: &'static str,
+    help: &'static str,
+    value: fn(&StatsEntry) -> f64,
+}
+
+const GAUGES: [Gauge; 5] = [
+    Gauge {
+        name: "fuzzer_instructions_covered",
+        help: "Instructions covered at the end of the run",
+        value: |e| e.instructions_covered as f64,
+    },
+    Gauge {
+        name: "fuzzer_instructions_total",
+        help: "Instructions in the contract",
+        value: |e| e.total_instructions as f64,
+    },
+    Gauge {
+        name: "fuzzer_branches_covered",
+        help: "Branches covered at the end of the run",
+        value: |e| e.branches_covered as f64,
+    },
+    Gauge {
+        name: "fuzzer_branches_total",
+        help: "Branches in the contract",
+        value: |e| e.total_branches as f64,
+    },
+    Gauge {
+        name: "fuzzer_last_sample_seconds",
+        help: "Time of the last coverage sample since the fuzzer started",
+        value: |e| e.time_taken_millis as f64 / 1_000.0,
+    },
+];
+
+/// Escapes a label value for the Prometheus text exposition format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Writes the final coverage of every contract run as Prometheus gauges,
+/// labelled with the contract id and the `--repeat` run index, for the
+/// node_exporter textfile collector. The file is written next to `path` and
+/// renamed into place so the collector never reads a partial file.
+pub fn write_prometheus_metrics(
+    path: &Path,
+    all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
+) -> Result<()> {
+    let mut run_ids: Vec<&String> = all_contract_stats.keys().collect();
+    run_ids.sort();
+    let last_samples: Vec<(&str, u32, &StatsEntry)> = run_ids
+        .into_iter()
+        .filter_map(|run_id| {
+            let last = all_contract_stats[run_id]
+                .iter()
+                .max_by_key(|e| e.time_taken_millis)?;
+            let (contract_id, run_index) = split_run_id(run_id);
+            Some((contract_id, run_index, last))
+        })
+        .collect();
+
+    let mut metrics = String::new();
+    for gauge in GAUGES {
+        let _ = writeln!(metrics, "# HELP {} {}", gauge.name, gauge.help);
+        let _ = writeln!(metrics, "# TYPE {} gauge", gauge.name);
+        for (contract_id, run_index, last) in &last_samples {
+            let _ = writeln!(
+                metrics,
+                "{}{{contract=\"{}\",run=\"{}\"}} {}",
+                gauge.name,
+                escape_label(contract_id),
+                run_index,
+                (gauge.value)(last)
+            );
+        }
+    }
+
+    // The textfile collector only reads `*.prom`, so the temporary name must not end in it
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = PathBuf::from(temp_name);
+    fs::write(&temp_path, metrics).wrap_err_with(|| {
+        format!(
+            "Failed to write Prometheus metrics to {}",
+            temp_path.display()
+        )
+    })?;
+    fs::rename(&temp_path, path).wrap_err_with(|| {
+        format!(
+            "Failed to move {} to {}",
+            temp_path.display(),
+            path.display()
+        )
+    })?;
+    info!(
+        "Prometheus metrics of {} runs written to {}",
+        last_samples.len(),
+        path.display()
+    );
+    Ok(())
+}
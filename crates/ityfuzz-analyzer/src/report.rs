@@ -0,0 +1,136 @@
+use crate::types::{ContractMeta, StatsEntry};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use eyre::{Result, WrapErr};
+use glob::glob;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// File name of the report written into the output directory
+pub const REPORT_FILE: &str = "report.html";
+
+const REPORT_STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+table{border-collapse:collapse}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:right}\
+th{cursor:pointer;background:#eee}\
+td:first-child,th:first-child{text-align:left}\
+img{max-width:100%}";
+
+/// Sorts a table by the clicked column, numerically when both cells parse as numbers
+const REPORT_SCRIPT: &str = "document.querySelectorAll('th').forEach((th,i)=>th.onclick=()=>{\
+const body=th.closest('table').tBodies[0];\
+const asc=th.dataset.asc!=='1';th.dataset.asc=asc?'1':'0';\
+const key=r=>r.cells[i].textContent;\
+[...body.rows].sort((a,b)=>{const x=key(a),y=key(b),nx=parseFloat(x),ny=parseFloat(y);\
+const c=isNaN(nx)||isNaN(ny)?x.localeCompare(y):nx-ny;return asc?c:-c;})\
+.forEach(r=>body.appendChild(r));});";
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn read_meta(output_dir: &Path, contract_id: &str) -> Option<ContractMeta> {
+    let meta_path = output_dir.join(format!("{}.meta.json", contract_id));
+    let content = fs::read_to_string(meta_path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn summary_table(
+    output_dir: &Path,
+    all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
+) -> String {
+    let mut contract_ids: Vec<&String> = all_contract_stats.keys().collect();
+    contract_ids.sort();
+
+    let mut table = String::from(
+        "<table><thead><tr><th>contract</th><th>instructions</th><th>total instructions</th>\
+         <th>coverage (%)</th><th>branches</th><th>samples</th><th>duration (s)</th>\
+         <th>exit</th></tr></thead><tbody>",
+    );
+    for contract_id in contract_ids {
+        let entries = &all_contract_stats[contract_id];
+        let Some(last) = entries.iter().max_by_key(|e| e.time_taken_millis) else {
+            continue;
+        };
+        let coverage_percent = last.instruction_percent().unwrap_or(0.0);
+        let exit_kind = read_meta(output_dir, contract_id)
+            .map_or_else(|| "-".to_string(), |meta| format!("{:?}", meta.exit_kind));
+        let _ = write!(
+            table,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.2}</td><td>{}</td><td>{}</td><td>{:.1}</td><td>{}</td></tr>",
+            escape_html(contract_id),
+            last.instructions_covered,
+            last.total_instructions,
+            coverage_percent,
+            last.branches_covered,
+            entries.len(),
+            last.time_taken_millis as f64 / 1_000.0,
+            exit_kind
+        );
+    }
+    table.push_str("</tbody></table>");
+    table
+}
+
+/// Writes a self-contained `report.html` to `output_dir` with the plots found
+/// there embedded as base64, a sortable per-contract summary table and the
+/// run manifest, if any. The title defaults to the output directory name.
+pub fn write_html_report(
+    output_dir: &Path,
+    title: Option<&str>,
+    all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
+) -> Result<PathBuf> {
+    let dir_name = output_dir.file_name().unwrap_or_default().to_string_lossy();
+    let title = title.unwrap_or(&dir_name);
+    let mut html = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{title}</title>\
+         <style>{REPORT_STYLE}</style></head><body><h1>{title}</h1>",
+        title = escape_html(title)
+    );
+
+    let png_glob_pattern = output_dir.join("*.png").to_string_lossy().into_owned();
+    let mut plot_paths: Vec<PathBuf> = glob(&png_glob_pattern)
+        .wrap_err_with(|| format!("Invalid glob pattern: '{}'", png_glob_pattern))?
+        .filter_map(|entry| entry.ok())
+        .collect();
+    plot_paths.sort();
+    if !plot_paths.is_empty() {
+        html.push_str("<h2>Plots</h2>");
+    }
+    for plot_path in &plot_paths {
+        let png = fs::read(plot_path)
+            .wrap_err_with(|| format!("Failed to read plot {}", plot_path.display()))?;
+        let name = plot_path.file_name().unwrap_or_default().to_string_lossy();
+        let _ = write!(
+            html,
+            "<figure><img alt=\"{name}\" src=\"data:image/png;base64,{}\"><figcaption>{name}</figcaption></figure>",
+            STANDARD.encode(png),
+            name = escape_html(&name)
+        );
+    }
+
+    html.push_str("<h2>Contracts</h2>");
+    html.push_str(&summary_table(output_dir, all_contract_stats));
+
+    if let Ok(manifest) = fs::read_to_string(output_dir.join("run_manifest.json")) {
+        let _ = write!(
+            html,
+            "<h2>Run manifest</h2><pre>{}</pre>",
+            escape_html(&manifest)
+        );
+    }
+
+    let _ = write!(html, "<script>{REPORT_SCRIPT}</script></body></html>");
+
+    let report_path = output_dir.join(REPORT_FILE);
+    fs::write(&report_path, html)
+        .wrap_err_with(|| format!("Failed to write report {}", report_path.display()))?;
+    info!("HTML report saved to {}", report_path.display());
+    Ok(report_path)
+}
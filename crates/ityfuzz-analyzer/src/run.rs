@@ -1,45 +1,87 @@
-use crate::plot::aggregate_and_plot_data;
+use crate::plot::{
+    PlotOptions, aggregate_and_plot_data, read_stats_from_csv, rename_into_place, temp_path,
+};
+use crate::progress::Progress;
+use crate::prometheus::write_prometheus_metrics;
+use crate::report::write_html_report;
+use crate::sqlite::write_stats_to_sqlite;
 use crate::types::RunArgs;
 use crate::types::StatsEntry;
-use csv::Writer;
+use crate::types::parse_matrix_axis;
+use crate::types::{
+    AnalysisWarning, BugEntry, CollisionPolicy, ContractMeta, ContractNameFrom, ExitKind,
+    GROUP_SEPARATOR, MatrixAxis, ProgressMode, ResourceLimits, RunManifest, RunOutcome,
+    TimeResolution, TimestampField, WorkdirCleanup,
+};
+use csv::WriterBuilder;
 use eyre::{Result, WrapErr, eyre};
 use glob::glob;
-use indicatif::{ProgressBar, ProgressStyle};
 use regex::Regex;
-use tracing::error;
-use std::collections::HashMap;
-use std::fs::{self};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Read};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, Stdio};
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
 use tracing::debug;
+use tracing::error;
 use tracing::info;
 use tracing::warn;
 
-pub fn handle_run_command(args: RunArgs) -> Result<()> {
-    fs::create_dir_all(&args.output_dir).wrap_err_with(|| {
-        format!(
-            "Failed to create output directory: {}",
-            args.output_dir.display()
-        )
-    })?;
+pub fn handle_run_command(mut args: RunArgs) -> Result<()> {
+    let execs_re = Regex::new(&args.execs_pattern)
+        .wrap_err_with(|| format!("Invalid --execs-pattern '{}'", args.execs_pattern))?;
+    if !execs_re.capture_names().any(|name| name == Some("execs")) {
+        return Err(eyre!(
+            "--execs-pattern '{}' has no (?P<execs>...) capture group",
+            args.execs_pattern
+        ));
+    }
+    let execs_re = &execs_re;
+    let bug_re = Regex::new(&args.bug_pattern)
+        .wrap_err_with(|| format!("Invalid --bug-pattern '{}'", args.bug_pattern))?;
+    if !bug_re.capture_names().any(|name| name == Some("timestamp")) {
+        return Err(eyre!(
+            "--bug-pattern '{}' has no (?P<timestamp>...) capture group",
+            args.bug_pattern
+        ));
+    }
+    let bug_re = &bug_re;
 
+    if args.fuzzer_cwd.is_some() {
+        // The work directories are passed to the fuzzer and read back here
+        args.workdir_root = std::path::absolute(&args.workdir_root)
+            .wrap_err_with(|| format!("Failed to resolve {}", args.workdir_root.display()))?;
+    }
 
-    let all_contract_stats: Arc<Mutex<HashMap<String, Vec<StatsEntry>>>> = Arc::new(Mutex::new(HashMap::new()));
+    args.fuzzer_options = merge_fuzzer_options(
+        &args.fuzzer_options,
+        args.fuzzer_options_file.as_deref(),
+        &args.target_flag,
+    )?;
+    let fuzzer_command = &fuzzer_command(&args)?;
 
-    let benchmark_glob_pattern = format!("{}/*", args.benchmark_base_dir.to_string_lossy());
+    let mut option_matrix = args.matrix.clone();
+    if let Some(path) = &args.option_matrix {
+        option_matrix.extend(read_option_matrix(path)?);
+    }
+    let matrix_flags: Vec<String> = option_matrix.iter().map(MatrixAxis::fuzzer_flag).collect();
+    check_fuzzer_options(&matrix_flags, &args.target_flag, "--matrix")?;
+    let combos = expand_option_matrix(&option_matrix);
 
-    let glob_pattern_results = glob(&benchmark_glob_pattern)
-        .wrap_err_with(|| format!("Invalid glob pattern: '{}'", benchmark_glob_pattern))?;
+    let all_contract_stats: Arc<Mutex<HashMap<String, Vec<StatsEntry>>>> =
+        Arc::new(Mutex::new(HashMap::new()));
 
-    let mut contract_dirs: Vec<PathBuf> = Vec::new();
-    for entry_result in glob_pattern_results {
-        let path = entry_result.wrap_err("Error processing a path from glob pattern")?;
-        if path.is_dir() {
-            contract_dirs.push(path);
-        }
-    }
+    let benchmark_base_dir = normalize_dir(&args.benchmark_base_dir);
+    let contract_dirs = find_contract_dirs(&benchmark_base_dir, args.depth, args.recursive)?;
 
     if contract_dirs.is_empty() {
         return Err(eyre!(
@@ -51,80 +93,366 @@ pub fn handle_run_command(args: RunArgs) -> Result<()> {
 
     info!("Found {} contract directories", contract_dirs.len());
 
-    let pb = ProgressBar::new(contract_dirs.len() as u64);
-    pb.set_style(
-        ProgressStyle::with_template(
-            "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})\n{msg}",
+    let mut contracts = assign_contract_ids(
+        &benchmark_base_dir,
+        contract_dirs,
+        args.contract_name_from,
+        args.on_collision,
+    )?;
+    let sample_seed = sample_contracts(&args, &mut contracts);
+    let num_contracts = contracts.len();
+
+    // One run per contract, option combination and repetition:
+    // (run id, contract dir, seed, combination arguments)
+    let mut runs: Vec<(String, PathBuf, Option<u64>, &[String])> = Vec::new();
+    for (contract_id, contract_dir_path) in contracts {
+        for (combo, combo_args) in &combos {
+            let combo_id = match combo {
+                Some(combo) => format!("{}{}{}", contract_id, GROUP_SEPARATOR, combo),
+                None => contract_id.clone(),
+            };
+            for repetition in 0..args.repeat {
+                let run_id = if args.repeat == 1 {
+                    combo_id.clone()
+                } else {
+                    format!("{}.run{}", combo_id, repetition + 1)
+                };
+                let seed = args
+                    .seed
+                    .map(|seed| seed.wrapping_add(u64::from(repetition)));
+                runs.push((run_id, contract_dir_path.clone(), seed, combo_args));
+            }
+        }
+    }
+
+    if args.list_contracts {
+        for (run_id, contract_dir_path, _, _) in &runs {
+            println!("{}\t{}", run_id, contract_dir_path.display());
+        }
+        println!(
+            "{} contract directories, {} runs",
+            num_contracts,
+            runs.len()
+        );
+        if let Some(seed) = sample_seed {
+            println!("Sampled with seed {}", seed);
+        }
+        return Ok(());
+    }
+
+    // create_dir_all only reports a bare "File exists" in this case
+    if args.output_dir.is_file() {
+        return Err(eyre!(
+            "Output path {} exists and is a file, not a directory",
+            args.output_dir.display()
+        ));
+    }
+    fs::create_dir_all(&args.output_dir).wrap_err_with(|| {
+        format!(
+            "Failed to create output directory: {}",
+            args.output_dir.display()
         )
-        .unwrap()
-        .progress_chars("█▓▒░ "),
-    );
+    })?;
+
+    let fuzzer_version = fuzzer_version(&args, fuzzer_command);
+    let manifest = RunManifest {
+        started_at: chrono::Utc::now().to_rfc3339(),
+        fuzzer_path: args.fuzzer_path.clone(),
+        fuzzer_version: fuzzer_version.clone(),
+        fuzzer_options: args.fuzzer_options.clone(),
+        fuzz_timeout_seconds: args.fuzz_timeout_seconds,
+        seed: args.seed,
+        repeat: args.repeat,
+        option_matrix,
+        timestamp_field: args.timestamp_field,
+        sample_seed,
+        runs: runs
+            .iter()
+            .map(|(run_id, _, _, _)| run_id.clone())
+            .collect(),
+    };
+    write_manifest(&manifest, &args.output_dir)?;
+
+    let pb = Progress::new(runs.len() as u64, args.progress);
     pb.set_message("Starting fuzzing...");
 
-    let num_threads = args.jobs;
+    let jobs = args
+        .jobs
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+        .min(runs.len());
+    let num_threads = cap_jobs(jobs, args.fuzzer_threads as usize);
 
     let pool = rayon::ThreadPoolBuilder::new()
         .num_threads(num_threads)
         .build()
         .wrap_err("Failed to create thread pool")?;
 
+    // Contracts whose results could not be written, e.g. on a full disk
+    let write_failures: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    let warnings: Mutex<Vec<AnalysisWarning>> = Mutex::new(Vec::new());
+    let aborted = AtomicBool::new(false);
+    let parse_options = ParseOptions {
+        verbose: args.verbose_parse,
+        resolution: args.time_resolution,
+        timestamp_field: args.timestamp_field,
+        exclude_zero_time: args.exclude_zero_time,
+        started_at_millis: None,
+    };
+    let outcomes: Mutex<BTreeMap<RunOutcome, usize>> = Mutex::new(BTreeMap::new());
+
     pool.scope(|s| {
-        for contract_dir_path in contract_dirs {
+        for (contract_id, contract_dir_path, seed, combo_args) in runs {
             let pb = pb.clone();
             let all_contract_stats = Arc::clone(&all_contract_stats);
+            let write_failures = &write_failures;
+            let warnings = &warnings;
+            let aborted = &aborted;
             let args = &args;
+            let record = |outcome| *outcomes.lock().unwrap().entry(outcome).or_default() += 1;
 
             s.spawn(move |_| {
                 pb.inc(1);
-                let contract_id = contract_dir_path
-                    .file_name()
-                    .expect("Contract directory should have a name")
-                    .to_string_lossy()
-                    .into_owned();
-
+                if aborted.load(Ordering::Relaxed) {
+                    record(RunOutcome::Skipped);
+                    return;
+                }
                 pb.set_message(format!("Fuzzing contract: {}", contract_id));
 
-                let contract_files_glob = format!("{}/*", contract_dir_path.to_string_lossy());
                 let mut options = vec![];
-                for option in args.fuzzer_options.iter() {
+                for option in args.fuzzer_options.iter().chain(combo_args) {
                     options.push(option.as_str());
                 }
 
                 let now = chrono::Utc::now().format("%Y-%m-%d_%H-%M-%S").to_string();
-                let work_dir = format!(".work-dirs/{}/{}", now, contract_id);
-                options.append(&mut vec!["-t", &contract_files_glob]);
+                let work_dir = args.workdir_root.join(&now).join(&contract_id).to_string_lossy().into_owned();
+                let targets_dir = PathBuf::from(format!("{}.targets", work_dir));
+                let mut work_dir_cleanup = WorkDirCleanup {
+                    dirs: vec![PathBuf::from(&work_dir), targets_dir.clone()],
+                    enabled: args.workdir_cleanup == WorkdirCleanup::Always,
+                };
+                let targets = match select_target_files(&contract_dir_path, &args.target_ext) {
+                    Ok(targets) => targets,
+                    Err(e) => {
+                        error!("Failed to collect target files for contract {}: {:?}", contract_id, e);
+                        record(RunOutcome::Skipped);
+                        return;
+                    }
+                };
+                // The fuzzer prints a start line but no coverage for empty targets
+                let empty_files: Vec<String> = targets
+                    .files
+                    .iter()
+                    .filter(|file| fs::metadata(file).is_ok_and(|m| m.len() == 0))
+                    .map(|file| file.display().to_string())
+                    .collect();
+                if !empty_files.is_empty() {
+                    warn!(
+                        "Skipping contract {} with empty target files: {}",
+                        contract_id,
+                        empty_files.join(", ")
+                    );
+                    warnings.lock().unwrap().push(AnalysisWarning::EmptyTarget {
+                        contract_id: contract_id.clone(),
+                        files: empty_files,
+                    });
+                    record(RunOutcome::Skipped);
+                    return;
+                }
+                let target_args = match target_args(args, &contract_dir_path, &targets, &targets_dir) {
+                    Ok(target_args) => target_args,
+                    Err(e) => {
+                        error!("Failed to collect target files for contract {}: {:?}", contract_id, e);
+                        record(RunOutcome::Skipped);
+                        return;
+                    }
+                };
+                options.extend(target_args.iter().map(String::as_str));
                 options.append(&mut vec!["-w", &work_dir]);
+                let seed_args = seed.map(|seed| seed_args(&args.seed_template, seed)).unwrap_or_default();
+                options.extend(seed_args.iter().map(String::as_str));
+
+                let on_execs = |execs: &str| {
+                    pb.set_live_message(format!("Fuzzing contract: {} ({} execs/sec)", contract_id, execs));
+                };
+                // Start time and samples of the stdout flushed so far
+                let flushed: RefCell<(Option<u64>, Vec<StatsEntry>)> = RefCell::new((None, Vec::new()));
+                let on_flush = |new_stdout: &str| {
+                    let (started_at_millis, entries) = &mut *flushed.borrow_mut();
+                    if started_at_millis.is_none() {
+                        match find_start_millis(new_stdout, parse_options.timestamp_field) {
+                            Ok(Some(started_at)) => *started_at_millis = Some(started_at),
+                            Ok(None) => return,
+                            Err(e) => {
+                                debug!("Skipping partial CSV flush of {}: {:?}", contract_id, e);
+                                return;
+                            }
+                        }
+                    }
+                    let flush_options = ParseOptions {
+                        verbose: false,
+                        started_at_millis: *started_at_millis,
+                        ..parse_options
+                    };
+                    let mut ignored_warnings = Vec::new();
+                    match parse_log(new_stdout, &contract_id, &flush_options, &mut ignored_warnings) {
+                        Ok(new_entries) if new_entries.is_empty() => {}
+                        Ok(new_entries) => {
+                            entries.extend(new_entries);
+                            dedup_samples(entries, parse_options.resolution);
+                            match write_csv(&contract_id, entries, &args.output_dir, false) {
+                                Ok(()) => debug!("Flushed {} partial entries of {}", entries.len(), contract_id),
+                                Err(e) => warn!("Failed to flush partial CSV of {}: {:?}", contract_id, e),
+                            }
+                        }
+                        Err(e) => debug!("Skipping partial CSV flush of {}: {:?}", contract_id, e),
+                    }
+                };
+                let timeout_seconds = contract_timeout(args, &contract_dir_path);
+                match run_program_with_timeout(
+                    args,
+                    fuzzer_command,
+                    &options[..],
+                    timeout_seconds,
+                    execs_re,
+                    &on_execs,
+                    &on_flush,
+                ) {
+                    Ok(output) => {
+                        let meta = ContractMeta {
+                            contract_id: contract_id.clone(),
+                            exit_code: output.exit_code,
+                            exit_kind: output.exit_kind,
+                            seed,
+                            env: args.env.iter().cloned().collect(),
+                            invocation: output.invocation,
+                            timeout_seconds: Some(timeout_seconds),
+                        };
+                        if let Err(e) = write_meta(&meta, &args.output_dir) {
+                            error!("Failed to write meta for contract {}: {:?}", contract_id, e);
+                        }
 
-                match run_program_with_timeout(&args.fuzzer_path, &options[..], args.fuzz_timeout_seconds) {
-                    Ok(log_content) => {
+                        let mut log_content = output.stdout;
+                        let mut invalid_utf8_lines = output.invalid_utf8_lines;
+                        if let Some(pattern) = &args.log_file_pattern {
+                            let log_path = pattern
+                                .replace("{workdir}", &work_dir)
+                                .replace("{contract}", &contract_id);
+                            match fs::read(&log_path) {
+                                Ok(bytes) => {
+                                    invalid_utf8_lines += count_invalid_utf8_lines(&bytes);
+                                    // Keep the last stdout line apart from the first log line
+                                    if !log_content.is_empty() && !log_content.ends_with('\n') {
+                                        log_content.push('\n');
+                                    }
+                                    log_content.push_str(&String::from_utf8_lossy(&bytes));
+                                }
+                                Err(e) => warn!(
+                                    "Failed to read fuzzer log file {} for {}, using stdout only: {}",
+                                    log_path, contract_id, e
+                                ),
+                            }
+                        }
+                        if invalid_utf8_lines > 0 {
+                            warn!(
+                                "{} output lines of {} had invalid UTF-8 bytes replaced, samples on them may be mangled",
+                                invalid_utf8_lines, contract_id
+                            );
+                            warnings.lock().unwrap().push(AnalysisWarning::InvalidUtf8 {
+                                contract_id: contract_id.clone(),
+                                lines: invalid_utf8_lines,
+                            });
+                        }
                         if log_content.trim().is_empty() {
                             info!(
                                 "No output from fuzzer for {}, skipping parsing (likely timeout or crash before output).",
                                 contract_id
                             );
+                            warnings.lock().unwrap().push(AnalysisWarning::EmptyLog {
+                                contract_id: contract_id.clone(),
+                            });
+                            record(match output.exit_kind {
+                                ExitKind::Timeout | ExitKind::Hung => RunOutcome::TimeoutNoOutput,
+                                _ => RunOutcome::EmptyLog,
+                            });
                             return;
                         }
-                        match parse_log(&log_content, &contract_id) {
+                        let mut contract_warnings = Vec::new();
+                        let parsed = parse_log(
+                            &log_content,
+                            &contract_id,
+                            &parse_options,
+                            &mut contract_warnings,
+                        );
+                        warnings.lock().unwrap().append(&mut contract_warnings);
+                        let mut bugs_write_failed = false;
+                        match parse_bugs(&log_content, bug_re, args.timestamp_field) {
+                            Ok(bugs) if bugs.is_empty() => {}
+                            Ok(bugs) => {
+                                info!("Found {} bugs for contract {}", bugs.len(), contract_id);
+                                if let Err(e) = write_bugs_csv(&contract_id, &bugs, &args.output_dir) {
+                                    error!("Failed to write bugs CSV for contract {}: {:?}", contract_id, e);
+                                    write_failures.lock().unwrap().push(contract_id.clone());
+                                    bugs_write_failed = true;
+                                }
+                            }
+                            Err(e) => warn!("Failed to parse bugs of contract {}: {:?}", contract_id, e),
+                        }
+                        match parsed {
                             Ok(entries) => {
                                 if entries.is_empty() {
                                     warn!(
                                         "No statistical entries parsed for {}, though log was not empty. Log content:\n'{}'",
                                         contract_id, log_content
                                     );
+                                    warnings.lock().unwrap().push(AnalysisWarning::EmptyLog {
+                                        contract_id: contract_id.clone(),
+                                    });
+                                    record(RunOutcome::EmptyLog);
                                 } else {
                                     info!(
                                         "Parsed {} entries for contract {}",
                                         entries.len(),
                                         contract_id
                                     );
-                                    write_csv(&contract_id, &entries, &args.output_dir).expect("Failed to write CSV");
+                                    if let Err(e) = write_csv(&contract_id, &entries, &args.output_dir, args.append) {
+                                        error!("Failed to write CSV for contract {}: {:?}", contract_id, e);
+                                        write_failures.lock().unwrap().push(contract_id.clone());
+                                        if args.abort_on_write_error {
+                                            aborted.store(true, Ordering::Relaxed);
+                                        }
+                                        record(RunOutcome::WriteError);
+                                        return;
+                                    }
                                     info!(
                                         "CSV saved for {} to {}/{}.instructions.stats.csv",
                                         contract_id,
                                         args.output_dir.display(),
                                         contract_id
                                     );
+                                    // Plot the whole accumulated CSV, not just this segment
+                                    let entries = if args.append {
+                                        let csv_path = args
+                                            .output_dir
+                                            .join(format!("{}.instructions.stats.csv", contract_id));
+                                        match read_stats_from_csv(&csv_path) {
+                                            Ok(all_entries) => all_entries,
+                                            Err(e) => {
+                                                warn!("Failed to re-read appended CSV for {}: {:?}", contract_id, e);
+                                                entries
+                                            }
+                                        }
+                                    } else {
+                                        entries
+                                    };
                                     all_contract_stats.lock().unwrap().insert(contract_id.clone(), entries);
+                                    if args.workdir_cleanup == WorkdirCleanup::OnSuccess {
+                                        work_dir_cleanup.enabled = true;
+                                    }
+                                    record(if bugs_write_failed {
+                                        RunOutcome::WriteError
+                                    } else {
+                                        RunOutcome::Succeeded
+                                    });
                                 }
                             }
                             Err(e) => {
@@ -132,21 +460,106 @@ pub fn handle_run_command(args: RunArgs) -> Result<()> {
                                     "Error parsing log for contract {}: {:?}\nLog content:\n{}",
                                     contract_id, e, log_content
                                 );
+                                warnings.lock().unwrap().push(AnalysisWarning::ParseFailure {
+                                    contract_id: contract_id.clone(),
+                                    message: format!("{:#}", e),
+                                });
+                                record(RunOutcome::ParseError);
                             }
                         }
                     }
                     Err(e) => {
                         info!("Error running fuzzer for contract {}: {:?}", contract_id, e);
+                        warnings.lock().unwrap().push(AnalysisWarning::ParseFailure {
+                            contract_id: contract_id.clone(),
+                            message: format!("{:#}", e),
+                        });
+                        record(RunOutcome::SpawnError);
                     }
                 }
             });
         }
     });
 
+    let summary = outcomes
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|(outcome, count)| format!("{}: {}", outcome.label(), count))
+        .collect::<Vec<_>>()
+        .join(", ");
+    info!("Run summary: {}", summary);
+    // --quiet sets the progress mode to none and keeps only errors on the console
+    if args.progress != ProgressMode::None {
+        println!("{}", summary);
+    }
+
+    let warnings = warnings.into_inner().unwrap();
+    write_warnings(&warnings, &args.output_dir)?;
+    if !warnings.is_empty() {
+        warn!(
+            "{} warnings recorded in {}",
+            warnings.len(),
+            args.output_dir.join(WARNINGS_FILE).display()
+        );
+    }
+
+    let write_failures = write_failures.into_inner().unwrap();
+    if args.include_empty {
+        write_empty_csvs(
+            &args,
+            &manifest.runs,
+            &write_failures,
+            &mut all_contract_stats.lock().unwrap(),
+        )?;
+    }
+    if !write_failures.is_empty() {
+        if args.abort_on_write_error {
+            return Err(eyre!(
+                "Aborted after failing to write results for: {}",
+                write_failures.join(", ")
+            ));
+        }
+        warn!(
+            "Failed to write results for {} contracts: {}",
+            write_failures.len(),
+            write_failures.join(", ")
+        );
+    }
+
+    if let Some(db_path) = &args.sqlite {
+        write_stats_to_sqlite(db_path, &all_contract_stats.lock().unwrap())?;
+    }
+    if let Some(prometheus_path) = &args.prometheus {
+        write_prometheus_metrics(prometheus_path, &all_contract_stats.lock().unwrap())?;
+    }
+
     if all_contract_stats.lock().unwrap().is_empty() {
         info!("No data collected from any contracts. Cannot generate aggregate plot.");
     } else {
-        aggregate_and_plot_data(&all_contract_stats.lock().unwrap(), &args.output_dir, None)?;
+        if args.no_plot {
+            info!(
+                "Skipping the aggregate plot (--no-plot); run the plot command later to create it."
+            );
+        } else {
+            aggregate_and_plot_data(
+                &all_contract_stats.lock().unwrap(),
+                &args.output_dir,
+                args.plot_title.clone(),
+                &PlotOptions {
+                    fuzzer_version,
+                    timeout_seconds: args.show_timeout.then_some(args.fuzz_timeout_seconds),
+                    ..PlotOptions::default()
+                },
+            )?;
+        }
+        if args.html_report {
+            write_html_report(
+                &args.output_dir,
+                args.plot_title.as_deref(),
+                &all_contract_stats.lock().unwrap(),
+            )?;
+        }
     }
 
     pb.finish_with_message(format!(
@@ -156,111 +569,1281 @@ pub fn handle_run_command(args: RunArgs) -> Result<()> {
     Ok(())
 }
 
-fn run_program_with_timeout(
+/// Work directory flag the run command appends for every contract
+const WORK_DIR_FLAGS: [&str; 2] = ["-w", "--work-dir"];
+
+/// ityfuzz accepts its target flag both as `-t` and `--target`
+const DEFAULT_TARGET_FLAGS: [&str; 2] = ["-t", "--target"];
+
+/// Rejects fuzzer options given with `source` that repeat a flag appended per
+/// contract, i.e. `target_flag` or the work directory, which would otherwise
+/// reach the fuzzer twice
+fn check_fuzzer_options(fuzzer_options: &[String], target_flag: &str, source: &str) -> Result<()> {
+    let target_flags = if DEFAULT_TARGET_FLAGS.contains(&target_flag) {
+        DEFAULT_TARGET_FLAGS.to_vec()
+    } else {
+        vec![target_flag]
+    };
+    let reserved = [
+        (
+            target_flags,
+            "the contract directories under --benchmark-base-dir (see --target-ext)",
+        ),
+        (
+            WORK_DIR_FLAGS.to_vec(),
+            "a per-contract directory under --workdir-root",
+        ),
+    ];
+    for option in fuzzer_options {
+        let flag = option.split('=').next().unwrap_or_default();
+        for (flags, handled_by) in &reserved {
+            if flags.contains(&flag) {
+                return Err(eyre!(
+                    "{} must not contain '{}': {} is set for each contract from {}",
+                    source,
+                    option,
+                    flags.join("/"),
+                    handled_by
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `--fuzzer-options` followed by the options of `--fuzzer-options-file` in
+/// file order, each checked against the flags set per contract
+fn merge_fuzzer_options(
+    cli_options: &[String],
+    options_file: Option<&Path>,
+    target_flag: &str,
+) -> Result<Vec<String>> {
+    check_fuzzer_options(cli_options, target_flag, "--fuzzer-options")?;
+    let mut fuzzer_options = cli_options.to_vec();
+    if let Some(path) = options_file {
+        let file_options = read_fuzzer_options_file(path)?;
+        check_fuzzer_options(&file_options, target_flag, "--fuzzer-options-file")?;
+        fuzzer_options.extend(file_options);
+    }
+    Ok(fuzzer_options)
+}
+
+/// Reads the options of a `--fuzzer-options-file`, in file order
+fn read_fuzzer_options_file(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read fuzzer options file {}", path.display()))?;
+    let mut options = Vec::new();
+    for (line_no, line) in content.lines().enumerate() {
+        // shell-words drops a `#` word and the rest of the line, but keeps a
+        // `#` inside quotes or a word, e.g. in a URL
+        let words = shell_words::split(line)
+            .map_err(|e| eyre!("{}:{}: {}", path.display(), line_no + 1, e))?;
+        options.extend(words);
+    }
+    Ok(options)
+}
+
+/// Reads the `--matrix` axes listed in an `--option-matrix` file
+fn read_option_matrix(path: &Path) -> Result<Vec<MatrixAxis>> {
+    let content = fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read option matrix {}", path.display()))?;
+    let mut axes = Vec::new();
+    for (line_no, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let axis = parse_matrix_axis(line)
+            .map_err(|e| eyre!("{}:{}: {}", path.display(), line_no + 1, e))?;
+        axes.push(axis);
+    }
+    Ok(axes)
+}
+
+/// Cartesian product of the matrix axes as (combination label, fuzzer
+/// arguments). The label joins `flag=value` pairs with `+`; without axes there
+/// is a single unlabeled combination.
+fn expand_option_matrix(axes: &[MatrixAxis]) -> Vec<(Option<String>, Vec<String>)> {
+    let mut combos: Vec<(Vec<String>, Vec<String>)> = vec![(Vec::new(), Vec::new())];
+    for axis in axes {
+        let flag = axis.fuzzer_flag();
+        let mut expanded = Vec::with_capacity(combos.len() * axis.values.len());
+        for (labels, combo_args) in &combos {
+            for value in &axis.values {
+                let mut labels = labels.clone();
+                labels.push(format!("{}={}", axis.flag.trim_start_matches('-'), value));
+                let mut combo_args = combo_args.clone();
+                combo_args.extend([flag.clone(), value.clone()]);
+                expanded.push((labels, combo_args));
+            }
+        }
+        combos = expanded;
+    }
+    combos
+        .into_iter()
+        .map(|(labels, combo_args)| ((!labels.is_empty()).then(|| labels.join("+")), combo_args))
+        .collect()
+}
+
+/// Fuzzing timeout of a contract. With `--timeout-scale`, the base timeout
+/// grows with the size of the contract's bytecode, up to `--max-timeout-seconds`.
+fn contract_timeout(args: &RunArgs, contract_dir_path: &Path) -> u64 {
+    let Some(seconds_per_kib) = args.timeout_scale else {
+        return args.fuzz_timeout_seconds;
+    };
+    let bytecode_bytes = match contract_artifact(contract_dir_path, "bin") {
+        // The .bin file holds the bytecode hex encoded
+        Ok(Some(bin_path)) => fs::metadata(&bin_path).map_or(0, |m| m.len()) / 2,
+        _ => {
+            debug!(
+                "No .bin file in {}, using the base timeout",
+                contract_dir_path.display()
+            );
+            0
+        }
+    };
+    let base = args.fuzz_timeout_seconds;
+    let scaled = base as f64 + seconds_per_kib * bytecode_bytes as f64 / 1024.0;
+    (scaled.round() as u64).clamp(base, args.max_timeout_seconds.max(base))
+}
+
+/// Limits `jobs` so that `jobs * fuzzer_threads` fits in the available cores
+fn cap_jobs(jobs: usize, fuzzer_threads: usize) -> usize {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    if jobs.saturating_mul(fuzzer_threads) <= cores {
+        return jobs;
+    }
+    let capped = (cores / fuzzer_threads).max(1);
+    warn!(
+        "{} jobs x {} fuzzer threads oversubscribes {} cores, running {} jobs instead",
+        jobs, fuzzer_threads, cores, capped
+    );
+    capped
+}
+
+/// Regular files of a contract directory passed to the fuzzer, and whether any
+/// other entry (a subdirectory or a file with another extension) was left out
+struct TargetFiles {
+    files: Vec<PathBuf>,
+    skipped_entries: bool,
+}
+
+fn select_target_files(contract_dir: &Path, target_ext: &[String]) -> Result<TargetFiles> {
+    let mut files = Vec::new();
+    let mut skipped_entries = false;
+    for entry in fs::read_dir(contract_dir).wrap_err_with(|| {
+        format!(
+            "Failed to read contract directory {}",
+            contract_dir.display()
+        )
+    })? {
+        let path = entry.wrap_err("Failed to read directory entry")?.path();
+        let ext_matches = target_ext.is_empty()
+            || path.extension().is_some_and(|ext| {
+                target_ext
+                    .iter()
+                    .any(|wanted| wanted.trim_start_matches('.') == ext.to_string_lossy())
+            });
+        if path.is_file() && ext_matches {
+            files.push(path);
+        } else {
+            skipped_entries = true;
+        }
+    }
+    files.sort();
+    Ok(TargetFiles {
+        files,
+        skipped_entries,
+    })
+}
+
+/// Builds the target arguments of a contract: the target flag with one glob,
+/// or with each target file for `--target-flag-repeat`
+fn target_args(
+    args: &RunArgs,
+    contract_dir: &Path,
+    targets: &TargetFiles,
+    targets_dir: &Path,
+) -> Result<Vec<String>> {
+    if !args.target_flag_repeat {
+        let glob = target_files_glob(contract_dir, targets, targets_dir)?;
+        return Ok(vec![args.target_flag.clone(), glob]);
+    }
+    if targets.files.is_empty() {
+        return Err(eyre!("No target files found in {}", contract_dir.display()));
+    }
+    Ok(targets
+        .files
+        .iter()
+        .flat_map(|file| {
+            [
+                args.target_flag.clone(),
+                file.to_string_lossy().into_owned(),
+            ]
+        })
+        .collect())
+}
+
+/// Builds the `-t` glob for a contract directory. When entries were left out of
+/// the target set, the selected files are linked into `targets_dir` so the glob
+/// matches only those.
+fn target_files_glob(
+    contract_dir: &Path,
+    targets: &TargetFiles,
+    targets_dir: &Path,
+) -> Result<String> {
+    let TargetFiles {
+        files,
+        skipped_entries,
+    } = targets;
+    if !skipped_entries {
+        return Ok(format!("{}/*", contract_dir.to_string_lossy()));
+    }
+    if files.is_empty() {
+        return Err(eyre!("No target files found in {}", contract_dir.display()));
+    }
+
+    fs::create_dir_all(targets_dir)
+        .wrap_err_with(|| format!("Failed to create {}", targets_dir.display()))?;
+    for file in files {
+        let file = fs::canonicalize(file)
+            .wrap_err_with(|| format!("Failed to resolve {}", file.display()))?;
+        let link = targets_dir.join(file.file_name().unwrap_or_default());
+        // A rerun within the same second reuses the targets directory
+        if link.symlink_metadata().is_ok() {
+            fs::remove_file(&link)
+                .wrap_err_with(|| format!("Failed to replace {}", link.display()))?;
+        }
+        #[cfg(unix)]
+        let linked = std::os::unix::fs::symlink(&file, &link);
+        #[cfg(not(unix))]
+        let linked = fs::copy(&file, &link).map(|_| ());
+        linked.wrap_err_with(|| {
+            format!(
+                "Failed to link {} into {}",
+                file.display(),
+                targets_dir.display()
+            )
+        })?;
+    }
+    debug!(
+        "Linked {} target files of {} into {}",
+        files.len(),
+        contract_dir.display(),
+        targets_dir.display()
+    );
+    Ok(format!("{}/*", targets_dir.to_string_lossy()))
+}
+
+/// Canonicalizes a user-supplied directory so that inputs like `b1/` or `b1/.`
+/// yield clean paths whose components can be used as contract ids
+fn normalize_dir(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.components().collect())
+}
+
+/// Collects the contract directories `depth` levels below `base_dir`, or with
+/// `recursive`, the first directories on each path that contain files.
+fn find_contract_dirs(base_dir: &Path, depth: u32, recursive: bool) -> Result<Vec<PathBuf>> {
+    if recursive {
+        let mut contract_dirs = Vec::new();
+        let mut pending = vec![base_dir.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let mut subdirs = Vec::new();
+            let mut has_files = false;
+            for entry in fs::read_dir(&dir)
+                .wrap_err_with(|| format!("Failed to read directory {}", dir.display()))?
+            {
+                let path = entry.wrap_err("Failed to read directory entry")?.path();
+                if path.is_dir() {
+                    subdirs.push(path);
+                } else {
+                    has_files = true;
+                }
+            }
+            if has_files && dir != base_dir {
+                contract_dirs.push(dir);
+            } else {
+                pending.extend(subdirs);
+            }
+        }
+        contract_dirs.sort();
+        return Ok(contract_dirs);
+    }
+
+    let mut benchmark_glob_pattern = base_dir.to_string_lossy().into_owned();
+    for _ in 0..depth {
+        benchmark_glob_pattern.push_str("/*");
+    }
+
+    let glob_pattern_results = glob(&benchmark_glob_pattern)
+        .wrap_err_with(|| format!("Invalid glob pattern: '{}'", benchmark_glob_pattern))?;
+
+    let mut contract_dirs: Vec<PathBuf> = Vec::new();
+    for entry_result in glob_pattern_results {
+        let path = entry_result.wrap_err("Error processing a path from glob pattern")?;
+        if path.is_dir() {
+            contract_dirs.push(path);
+        }
+    }
+    Ok(contract_dirs)
+}
+
+/// Compiled artifact with extension `ext` in a contract directory. With several,
+/// the largest is taken, usually the main contract that embeds the others.
+fn contract_artifact(contract_dir_path: &Path, ext: &str) -> Result<Option<PathBuf>> {
+    let mut artifacts = Vec::new();
+    for entry in fs::read_dir(contract_dir_path)
+        .wrap_err_with(|| format!("Failed to read {}", contract_dir_path.display()))?
+    {
+        let path = entry
+            .wrap_err_with(|| format!("Failed to read {}", contract_dir_path.display()))?
+            .path();
+        if path.is_file() && path.extension().is_some_and(|e| e == ext) {
+            let len = fs::metadata(&path).map_or(0, |m| m.len());
+            artifacts.push((len, path));
+        }
+    }
+    // Largest first, ties broken by name so every run picks the same file
+    artifacts.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    Ok(artifacts.into_iter().next().map(|(_, path)| path))
+}
+
+/// Contract id of a contract directory: its path below `base_dir` with the
+/// intermediate group directories joined by `GROUP_SEPARATOR`. With
+/// `name_from` other than `Dir`, the last component is the stem of the compiled
+/// artifact, if the directory has one. Also returns the path the name was taken
+/// from.
+fn contract_id_of(
+    base_dir: &Path,
+    contract_dir_path: &Path,
+    name_from: ContractNameFrom,
+) -> Result<(String, PathBuf)> {
+    let relative = contract_dir_path
+        .strip_prefix(base_dir)
+        .unwrap_or(contract_dir_path);
+    let mut components: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    if components.is_empty() {
+        return Err(eyre!(
+            "Could not get file name from path: {:?}",
+            contract_dir_path
+        ));
+    }
+    let artifact = match name_from.extension() {
+        Some(ext) => contract_artifact(contract_dir_path, ext)?,
+        None => None,
+    };
+    let named_from = match artifact {
+        Some(artifact) => {
+            if let (Some(last), Some(stem)) = (components.last_mut(), artifact.file_stem()) {
+                *last = stem.to_string_lossy().into_owned();
+            }
+            artifact
+        }
+        None => {
+            if name_from != ContractNameFrom::Dir {
+                debug!(
+                    "No {:?} artifact in {}, using the directory name",
+                    name_from,
+                    contract_dir_path.display()
+                );
+            }
+            contract_dir_path.to_path_buf()
+        }
+    };
+    Ok((components.join(GROUP_SEPARATOR), named_from))
+}
+
+/// Removes the work directories of a contract run when dropped if `enabled`,
+/// so that every exit path of the run is covered
+struct WorkDirCleanup {
+    dirs: Vec<PathBuf>,
+    enabled: bool,
+}
+
+impl Drop for WorkDirCleanup {
+    fn drop(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        for dir in &self.dirs {
+            if !dir.exists() {
+                continue;
+            }
+            debug!("Removing work directory {}", dir.display());
+            if let Err(e) = fs::remove_dir_all(dir) {
+                warn!("Failed to remove work directory {}: {:?}", dir.display(), e);
+            }
+            // Drop the per-invocation timestamp directory once it is empty
+            if let Some(parent) = dir.parent() {
+                let _ = fs::remove_dir(parent);
+            }
+        }
+    }
+}
+
+/// Keeps the random subset of `contracts` selected by `--sample` or
+/// `--sample-n`, in their original order, and returns the seed of the choice
+fn sample_contracts(args: &RunArgs, contracts: &mut Vec<(String, PathBuf)>) -> Option<u64> {
+    let num_contracts = contracts.len();
+    let num_sampled = match (args.sample, args.sample_n) {
+        (Some(fraction), _) => ((fraction * num_contracts as f64).ceil() as usize).max(1),
+        (None, Some(n)) => n as usize,
+        (None, None) => return None,
+    }
+    .min(num_contracts);
+    let seed = args.seed.unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+    });
+
+    // Partial Fisher-Yates shuffle driven by SplitMix64
+    let mut state = seed;
+    let mut next_random = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    let mut indices: Vec<usize> = (0..num_contracts).collect();
+    for i in 0..num_sampled {
+        let j = i + (next_random() % (num_contracts - i) as u64) as usize;
+        indices.swap(i, j);
+    }
+    let selected: HashSet<usize> = indices[..num_sampled].iter().copied().collect();
+    let mut index = 0;
+    contracts.retain(|_| {
+        index += 1;
+        selected.contains(&(index - 1))
+    });
+
+    let ids: Vec<&str> = contracts
+        .iter()
+        .map(|(contract_id, _)| contract_id.as_str())
+        .collect();
+    info!(
+        "Sampled {} of {} contracts with seed {}: {}",
+        num_sampled,
+        num_contracts,
+        seed,
+        ids.join(", ")
+    );
+    Some(seed)
+}
+
+/// Pairs each contract directory with its contract id, resolving ids shared
+/// by several directories according to `policy`.
+fn assign_contract_ids(
+    base_dir: &Path,
+    contract_dirs: Vec<PathBuf>,
+    name_from: ContractNameFrom,
+    policy: CollisionPolicy,
+) -> Result<Vec<(String, PathBuf)>> {
+    let mut named_dirs = Vec::with_capacity(contract_dirs.len());
+    let mut dirs_by_id: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for contract_dir_path in contract_dirs {
+        let (contract_id, named_from) = contract_id_of(base_dir, &contract_dir_path, name_from)?;
+        dirs_by_id
+            .entry(contract_id.clone())
+            .or_default()
+            .push(contract_dir_path.clone());
+        named_dirs.push((contract_id, named_from, contract_dir_path));
+    }
+
+    let collisions: Vec<(&String, &Vec<PathBuf>)> = dirs_by_id
+        .iter()
+        .filter(|(_, dirs)| dirs.len() > 1)
+        .collect();
+    for (contract_id, dirs) in &collisions {
+        warn!(
+            "Contract id '{}' is shared by {} directories: {:?}",
+            contract_id,
+            dirs.len(),
+            dirs
+        );
+    }
+    if policy == CollisionPolicy::Error && !collisions.is_empty() {
+        return Err(eyre!(
+            "Found {} colliding contract ids: {:?}",
+            collisions.len(),
+            collisions
+        ));
+    }
+
+    let mut contracts = Vec::with_capacity(named_dirs.len());
+    for (dir_name, named_from, contract_dir_path) in named_dirs {
+        let collides = dirs_by_id[&dir_name].len() > 1;
+        let contract_id = if collides && policy == CollisionPolicy::Rename {
+            // The contract directory for ids taken from an artifact
+            let parent_name = named_from
+                .parent()
+                .and_then(|parent| parent.file_name())
+                .unwrap_or_default()
+                .to_string_lossy();
+            format!("{}_{}", parent_name, dir_name)
+        } else {
+            dir_name
+        };
+        contracts.push((contract_id, contract_dir_path));
+    }
+    Ok(contracts)
+}
+
+/// Installs a pre-exec hook applying `limits` to the spawned process. The
+/// limits are inherited by the fuzzer that `timeout` execs.
+#[cfg(unix)]
+fn apply_resource_limits(command: &mut Command, limits: &ResourceLimits) {
+    use std::os::unix::process::CommandExt;
+
+    if limits.mem_limit_mb.is_none() && limits.cpu_affinity.is_empty() {
+        return;
+    }
+    let mem_limit_bytes = limits.mem_limit_mb.map(|mb| mb.saturating_mul(1024 * 1024));
+    let cpu_affinity = limits.cpu_affinity.clone();
+
+    // SAFETY: the hook only issues async-signal-safe syscalls and does not
+    // allocate, as required between fork and exec.
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(bytes) = mem_limit_bytes {
+                let rlimit = libc::rlimit {
+                    rlim_cur: bytes as libc::rlim_t,
+                    rlim_max: bytes as libc::rlim_t,
+                };
+                if libc::setrlimit(libc::RLIMIT_AS, &rlimit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            #[cfg(target_os = "linux")]
+            if !cpu_affinity.is_empty() {
+                let mut set: libc::cpu_set_t = std::mem::zeroed();
+                for &cpu in &cpu_affinity {
+                    libc::CPU_SET(cpu, &mut set);
+                }
+                if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn apply_resource_limits(_command: &mut Command, limits: &ResourceLimits) {
+    if limits.mem_limit_mb.is_some() || !limits.cpu_affinity.is_empty() {
+        warn!("--mem-limit-mb and --cpu-affinity are only supported on Unix; ignoring.");
+    }
+}
+
+/// Asks the `timeout` wrapper to stop; it forwards SIGTERM to the fuzzer so
+/// the fuzzer is not left orphaned.
+#[cfg(unix)]
+fn terminate_child(child: &mut Child) {
+    // SAFETY: `kill` has no memory-safety preconditions.
+    if unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) } != 0 {
+        let _ = child.kill();
+    }
+}
+
+#[cfg(not(unix))]
+fn terminate_child(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// Captured result of a fuzzer invocation
+struct ProgramOutput {
+    stdout: String,
+    /// Fuzzer program and arguments, without the `timeout` wrapper
+    invocation: Vec<String>,
+    /// Stdout lines whose invalid UTF-8 bytes were replaced
+    invalid_utf8_lines: usize,
+    exit_code: Option<i32>,
+    exit_kind: ExitKind,
+}
+
+/// A line read from the fuzzer's stdout or stderr
+enum OutputLine {
+    Stdout(String),
+    Stderr(String),
+}
+
+/// Forwards every line of `output` to `tx`, decoding invalid UTF-8 lossily.
+/// Returns the number of lines that had invalid bytes replaced.
+fn spawn_line_reader<R: Read + Send + 'static>(
+    output: R,
+    tx: mpsc::Sender<OutputLine>,
+    wrap: fn(String) -> OutputLine,
+) -> thread::JoinHandle<usize> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(output);
+        let mut buf = Vec::new();
+        let mut invalid_utf8_lines = 0;
+        while matches!(reader.read_until(b'\n', &mut buf), Ok(n) if n > 0) {
+            // Only a lossy replacement yields an owned string
+            let line = match String::from_utf8_lossy(&buf) {
+                Cow::Borrowed(line) => line.to_string(),
+                Cow::Owned(line) => {
+                    invalid_utf8_lines += 1;
+                    line
+                }
+            };
+            if tx.send(wrap(line)).is_err() {
+                break;
+            }
+            buf.clear();
+        }
+        invalid_utf8_lines
+    })
+}
+
+/// Number of lines of `bytes` that are not valid UTF-8
+fn count_invalid_utf8_lines(bytes: &[u8]) -> usize {
+    bytes
+        .split(|&b| b == b'\n')
+        .filter(|line| std::str::from_utf8(line).is_err())
+        .count()
+}
+
+fn classify_exit_code(exit_code: Option<i32>, success_exit_codes: &[i32]) -> ExitKind {
+    match exit_code {
+        Some(0) => ExitKind::Success,
+        Some(124) => ExitKind::Timeout,
+        Some(code) if success_exit_codes.contains(&code) => ExitKind::AcceptedExitCode,
+        _ => ExitKind::Failure,
+    }
+}
+
+/// Refines the exit kind of a run: a fuzzer killed by the timeout whose last
+/// coverage line is `since_coverage` ago, if any, counts as hung once that is
+/// at least `hang_limit`
+fn classify_run(
+    exit_kind: ExitKind,
+    plateau_stopped: bool,
+    since_coverage: Option<Duration>,
+    hang_limit: Duration,
+) -> ExitKind {
+    match exit_kind {
+        _ if plateau_stopped => ExitKind::PlateauStopped,
+        ExitKind::Timeout if since_coverage.is_none_or(|since| since >= hang_limit) => {
+            ExitKind::Hung
+        }
+        exit_kind => exit_kind,
+    }
+}
+
+/// Attempts to start a fuzzer before a transient spawn failure is reported
+const SPAWN_ATTEMPTS: u32 = 4;
+/// Delay before the first spawn retry, doubled for each further retry
+const SPAWN_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Spawns `command`, retrying with exponential backoff while spawning fails
+/// with EAGAIN, e.g. when many jobs start processes at the same time
+fn spawn_with_backoff(command: &mut Command, program_path: &str) -> Result<Child> {
+    retry_spawn(program_path, SPAWN_BACKOFF, || command.spawn())
+}
+
+/// Calls `spawn` until it succeeds, fails with an error other than EAGAIN, or
+/// `SPAWN_ATTEMPTS` are used up, sleeping `delay` before the first retry
+fn retry_spawn<T>(
     program_path: &str,
+    mut delay: Duration,
+    mut spawn: impl FnMut() -> std::io::Result<T>,
+) -> Result<T> {
+    let mut attempt = 1;
+    loop {
+        match spawn() {
+            Ok(spawned) => return Ok(spawned),
+            Err(e) if attempt < SPAWN_ATTEMPTS && e.kind() == std::io::ErrorKind::WouldBlock => {
+                warn!(
+                    "Failed to start program {} (attempt {}/{}): {}; retrying in {:?}",
+                    program_path, attempt, SPAWN_ATTEMPTS, e, delay
+                );
+                thread::sleep(delay);
+                attempt += 1;
+                delay *= 2;
+            }
+            Err(e) if attempt > 1 => {
+                return Err(e).wrap_err_with(|| {
+                    format!(
+                        "Failed to start program {} after {} attempts",
+                        program_path, attempt
+                    )
+                });
+            }
+            Err(e) => {
+                return Err(e)
+                    .wrap_err_with(|| format!("Failed to start program {}", program_path));
+            }
+        }
+    }
+}
+
+/// Program and leading arguments the fuzzer is started with. A `--fuzzer-path`
+/// that is not an existing file is split into words, or with `--fuzzer-shell`
+/// run by `sh -c` with the per-contract arguments appended as `"$@"`.
+fn fuzzer_command(run_args: &RunArgs) -> Result<Vec<String>> {
+    let fuzzer_path = &run_args.fuzzer_path;
+    if run_args.fuzzer_shell {
+        return Ok(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            format!("{} \"$@\"", fuzzer_path),
+            "sh".to_string(),
+        ]);
+    }
+    // A path containing spaces is still a single program
+    if Path::new(fuzzer_path).is_file() {
+        return Ok(vec![fuzzer_path.clone()]);
+    }
+    let words = shell_words::split(fuzzer_path)
+        .wrap_err_with(|| format!("Invalid --fuzzer-path '{}'", fuzzer_path))?;
+    if words.is_empty() {
+        return Err(eyre!("--fuzzer-path is empty"));
+    }
+    Ok(words)
+}
+
+/// Seconds the fuzzer may take to print its version
+const FUZZER_VERSION_TIMEOUT_SECONDS: u64 = 10;
+
+/// Version of the fuzzer: the first output line of `--fuzzer-version-flag`,
+/// falling back to the mtime and FNV-1a hash of the fuzzer binary
+fn fuzzer_version(run_args: &RunArgs, fuzzer_command: &[String]) -> Option<String> {
+    let mut command = Command::new("timeout");
+    command
+        .arg(FUZZER_VERSION_TIMEOUT_SECONDS.to_string())
+        .args(fuzzer_command)
+        .arg(&run_args.fuzzer_version_flag)
+        .stdin(Stdio::null());
+    if let Some(fuzzer_cwd) = &run_args.fuzzer_cwd {
+        command.current_dir(fuzzer_cwd);
+    }
+    match command.output() {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let version = stdout
+                .lines()
+                .chain(stderr.lines())
+                .map(str::trim)
+                .find(|line| !line.is_empty());
+            if let Some(version) = version {
+                info!("Fuzzer version: {}", version);
+                return Some(version.to_string());
+            }
+        }
+        Ok(output) => debug!(
+            "Fuzzer {} {} exited with {}",
+            run_args.fuzzer_path, run_args.fuzzer_version_flag, output.status
+        ),
+        Err(e) => debug!(
+            "Failed to run fuzzer {} for its version: {}",
+            run_args.fuzzer_path, e
+        ),
+    }
+
+    let Some(binary) = resolve_program(fuzzer_command.first()?, run_args.fuzzer_cwd.as_deref())
+    else {
+        warn!("Fuzzer printed no version and its binary was not found");
+        return None;
+    };
+    match binary_fingerprint(&binary) {
+        Ok(fingerprint) => {
+            info!(
+                "Fuzzer printed no version, identifying it by {}",
+                fingerprint
+            );
+            Some(fingerprint)
+        }
+        Err(e) => {
+            warn!("Could not determine the fuzzer version: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Path of the file `program` starts, like `Command` finds it: bare names
+/// are looked up in `PATH`, other relative paths in `cwd`
+fn resolve_program(program: &str, cwd: Option<&Path>) -> Option<PathBuf> {
+    let program_path = Path::new(program);
+    if program_path.components().count() > 1 {
+        let path = match cwd {
+            Some(cwd) => cwd.join(program_path),
+            None => program_path.to_path_buf(),
+        };
+        return path.is_file().then_some(path);
+    }
+    env::var_os("PATH").and_then(|paths| {
+        env::split_paths(&paths)
+            .map(|dir| dir.join(program))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Modification time and FNV-1a hash of a file, e.g.
+/// `mtime 2025-06-11T07:14:44+00:00, fnv1a 3c7e0f5d9a1b2c4d`
+fn binary_fingerprint(path: &Path) -> Result<String> {
+    let mtime = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .wrap_err_with(|| format!("Failed to read the modification time of {}", path.display()))?;
+    let mut reader = BufReader::new(
+        fs::File::open(path).wrap_err_with(|| format!("Failed to open {}", path.display()))?,
+    );
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    loop {
+        let buf = reader
+            .fill_buf()
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        if buf.is_empty() {
+            break;
+        }
+        for &b in buf {
+            hash = (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3);
+        }
+        let len = buf.len();
+        reader.consume(len);
+    }
+    Ok(format!(
+        "mtime {}, fnv1a {:016x}",
+        chrono::DateTime::<chrono::Utc>::from(mtime)
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        hash
+    ))
+}
+
+/// Runs `fuzzer_command` with `args` under `timeout_seconds` and the limits
+/// and environment of `run_args`. `on_execs` is called with every execs/sec
+/// value matched by `execs_re` on the fuzzer's stderr, `on_flush` with the
+/// stdout printed since its previous call as set by `--flush-every-samples` and
+/// `--flush-every-seconds`.
+fn run_program_with_timeout(
+    run_args: &RunArgs,
+    fuzzer_command: &[String],
     args: &[&str],
     timeout_seconds: u64,
-) -> Result<String> {
+    execs_re: &Regex,
+    on_execs: &dyn Fn(&str),
+    on_flush: &dyn Fn(&str),
+) -> Result<ProgramOutput> {
+    let program_path = run_args.fuzzer_path.as_str();
+    let plateau_seconds = run_args.plateau_seconds;
     info!(
         "Running program {} with args {:?} and timeout {}s",
         program_path, args, timeout_seconds
     );
 
     let timeout_str = timeout_seconds.to_string();
+    // Never inherit the terminal, a fuzzer reading stdin would block on it
+    let stdin = match &run_args.fuzzer_stdin {
+        Some(path) => Stdio::from(
+            fs::File::open(path)
+                .wrap_err_with(|| format!("Failed to open fuzzer stdin file {}", path.display()))?,
+        ),
+        None => Stdio::null(),
+    };
 
-    let child = Command::new("timeout")
-        .args([&timeout_str, program_path])
+    let mut command = Command::new("timeout");
+    command
+        .arg(&timeout_str)
+        .args(fuzzer_command)
         .args(args)
+        .envs(run_args.env.iter().map(|(key, value)| (key, value)))
+        .stdin(stdin)
         .stdout(Stdio::piped())
-        .stderr(Stdio::piped()) // Capture stderr
-        .spawn()
-        .wrap_err_with(|| format!("Failed to start program {}", program_path))?;
-
-    let output = child.wait_with_output()?;
-    let stdout_str = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr_str = String::from_utf8_lossy(&output.stderr).to_string();
-
-    if !output.status.success() {
-        if !stderr_str.is_empty() {
-            error!(
-                "Stderr from running {} {:?}:\n{}",
-                program_path,
-                &args,
-                stderr_str.trim()
-            );
+        .stderr(Stdio::piped()); // Capture stderr
+    if let Some(fuzzer_cwd) = &run_args.fuzzer_cwd {
+        command.current_dir(fuzzer_cwd);
+    }
+    apply_resource_limits(&mut command, &run_args.limits);
+
+    let mut child = spawn_with_backoff(&mut command, program_path)?;
+
+    // Stream both outputs line by line so coverage and execs/sec can be
+    // watched while the fuzzer runs.
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let (line_tx, line_rx) = mpsc::channel::<OutputLine>();
+    let stdout_reader = spawn_line_reader(stdout, line_tx.clone(), OutputLine::Stdout);
+    let stderr_reader = spawn_line_reader(stderr, line_tx, OutputLine::Stderr);
+
+    let coverage_re =
+        Regex::new(COVERAGE_STAT_PATTERN).wrap_err("Failed to compile 'coverage stat' regex")?;
+    let mut stdout_str = String::new();
+    let mut stderr_str = String::new();
+    let mut max_covered: Option<u64> = None;
+    let mut last_increase = Instant::now();
+    let mut last_coverage: Option<Instant> = None;
+    let mut plateau_stopped = false;
+    let mut unflushed_samples = 0;
+    let mut flushed_len = 0;
+    let mut last_flush = Instant::now();
+
+    loop {
+        match line_rx.recv_timeout(Duration::from_millis(500)) {
+            Ok(OutputLine::Stderr(line)) => {
+                if let Some(execs) = execs_re.captures(&line).and_then(|caps| caps.name("execs")) {
+                    on_execs(execs.as_str());
+                }
+                stderr_str.push_str(&line);
+            }
+            Ok(OutputLine::Stdout(line)) => {
+                let mut covered: Option<u64> = None;
+                for caps in coverage_re.captures_iter(&line) {
+                    unflushed_samples += 1;
+                    covered = covered.max(caps["instructions_covered"].parse::<u64>().ok());
+                }
+                if let Some(covered) = covered {
+                    // Only coverage lines count as progress for --hang-seconds
+                    last_coverage = Some(Instant::now());
+                    if max_covered.is_none_or(|max| covered > max) {
+                        max_covered = Some(covered);
+                        last_increase = Instant::now();
+                    }
+                }
+                stdout_str.push_str(&line);
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
         }
-        if output.status.code() == Some(124) {
+
+        let flush_due = run_args
+            .flush_every_samples
+            .is_some_and(|samples| unflushed_samples >= samples)
+            || run_args.flush_every_seconds.is_some_and(|seconds| {
+                stdout_str.len() > flushed_len
+                    && last_flush.elapsed() >= Duration::from_secs(seconds)
+            });
+        if flush_due {
+            on_flush(&stdout_str[flushed_len..]);
+            unflushed_samples = 0;
+            flushed_len = stdout_str.len();
+            last_flush = Instant::now();
+        }
+
+        // The plateau clock starts at the first coverage stat, so start-up
+        // time is not counted against the contract.
+        if let (Some(limit), Some(_)) = (plateau_seconds, max_covered) {
+            if !plateau_stopped && last_increase.elapsed() >= Duration::from_secs(limit) {
+                info!(
+                    "Coverage of {} {:?} has not increased for {}s, stopping early.",
+                    program_path, &args, limit
+                );
+                terminate_child(&mut child);
+                plateau_stopped = true;
+            }
+        }
+    }
+
+    let status = child.wait()?;
+    let invalid_utf8_lines = stdout_reader.join().unwrap_or(0);
+    let _ = stderr_reader.join();
+
+    let exit_code = status.code();
+    let hang_seconds = run_args.hang_seconds.unwrap_or(timeout_seconds / 4);
+    let exit_kind = classify_run(
+        classify_exit_code(exit_code, &run_args.success_exit_codes),
+        plateau_stopped,
+        last_coverage.map(|at| at.elapsed()),
+        Duration::from_secs(hang_seconds),
+    );
+
+    if !status.success() && !plateau_stopped && !stderr_str.is_empty() {
+        error!(
+            "Stderr from running {} {:?}:\n{}",
+            program_path,
+            &args,
+            stderr_str.trim()
+        );
+    }
+
+    match exit_kind {
+        ExitKind::Success => {}
+        ExitKind::Timeout => {
             info!("Program {} {:?} timed out.", program_path, &args);
-        } else {
+        }
+        ExitKind::Hung => {
+            warn!(
+                "Program {} {:?} timed out with no output in its last {}s, it may have hung.",
+                program_path, &args, hang_seconds
+            );
+        }
+        ExitKind::AcceptedExitCode => {
+            info!(
+                "Program {} {:?} exited with accepted status {}.",
+                program_path, &args, status
+            );
+        }
+        ExitKind::Failure => {
             info!(
                 "Program {} {:?} exited with status {}.",
-                program_path, &args, output.status
+                program_path, &args, status
+            );
+        }
+        ExitKind::PlateauStopped => {
+            info!(
+                "Program {} {:?} was stopped after its coverage plateaued.",
+                program_path, &args
             );
         }
     }
 
-    Ok(stdout_str)
+    let mut invocation = fuzzer_command.to_vec();
+    invocation.extend(args.iter().map(|arg| arg.to_string()));
+
+    Ok(ProgramOutput {
+        stdout: stdout_str,
+        invocation,
+        invalid_utf8_lines,
+        exit_code,
+        exit_kind,
+    })
 }
 
-fn parse_log(log_content: &str, contract_id: &str) -> Result<Vec<StatsEntry>> {
+/// Coverage stat line printed by ityfuzz, e.g.
+/// `INFO Coverage stat: time-millis: 1749628484080 instructions: 957/2248 branches: 49/112`
+/// Fields may be separated by any run of spaces or tabs. Some builds log
+/// `time-nanos` or `time-seconds` instead, see `--timestamp-field`, and some
+/// append the number of executions so far as `execs: 120000`.
+const COVERAGE_STAT_PATTERN: &str = r"Coverage stat:\s+time-(?:millis|nanos|seconds):\s+(?P<timestamp>\d+)\s+instructions:\s+(?P<instructions_covered>\d+)/(?P<total_instructions>\d+)\s+branches:\s+(?P<branches_covered>\d+)/(?P<total_branches>\d+)(?:\s+execs:\s+(?P<execs>\d+))?";
+
+/// Coverage stat line of fuzzers that report a percentage instead of counts, e.g.
+/// `INFO Coverage stat: time-millis: 1749628484080 coverage: 42.5%`
+const COVERAGE_PERCENT_PATTERN: &str = r"Coverage stat:\s+time-(?:millis|nanos|seconds):\s+(?P<timestamp>\d+)\s+coverage:\s+(?P<coverage_percent>\d+(?:\.\d+)?)%(?:\s+execs:\s+(?P<execs>\d+))?";
+
+/// Start line of ityfuzz, e.g. `INFO Ityfuzz start at 1749625856722`
+const START_PATTERN: &str = r".*Ityfuzz start at (\d+)";
+
+/// Gap between consecutive coverage samples above which a warning is logged
+const SUSPICIOUS_SAMPLE_GAP_MILLIS: u64 = 60 * 60 * 1000;
+
+/// Maximum number of unmatched lines reported per log with `--verbose-parse`
+const MAX_UNMATCHED_LINES_LOGGED: usize = 50;
+
+/// Settings of `parse_log`
+#[derive(Debug, Clone, Copy)]
+pub struct ParseOptions {
+    /// Log lines matching neither pattern at debug level
+    pub verbose: bool,
+    /// Bucket size samples are deduplicated per
+    pub resolution: TimeResolution,
+    /// Unit of the log timestamps, which are converted to milliseconds
+    pub timestamp_field: TimestampField,
+    /// Drop samples at the start time
+    pub exclude_zero_time: bool,
+    /// Start time in milliseconds, for a part of a log whose start line was
+    /// in an earlier part
+    pub started_at_millis: Option<u64>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            verbose: false,
+            resolution: TimeResolution::Ms,
+            timestamp_field: TimestampField::Millis,
+            exclude_zero_time: false,
+            started_at_millis: None,
+        }
+    }
+}
+
+/// Parses the coverage stats of one contract from ityfuzz's stdout as set by
+/// `options`. Problems with the log are also pushed to `warnings`.
+pub fn parse_log(
+    log_content: &str,
+    contract_id: &str,
+    options: &ParseOptions,
+    warnings: &mut Vec<AnalysisWarning>,
+) -> Result<Vec<StatsEntry>> {
+    let ParseOptions {
+        verbose: verbose_parse,
+        resolution,
+        timestamp_field,
+        exclude_zero_time,
+        started_at_millis,
+    } = *options;
     let mut entries = Vec::new();
-    // parse start time from
-    // INFO Ityfuzz start at 1749625856722
-    let start_re =
-        Regex::new(r".*Ityfuzz start at (\d+)").wrap_err("Failed to compile 'start at' regex")?;
+    let start_re = Regex::new(START_PATTERN).wrap_err("Failed to compile 'start at' regex")?;
     // parse coverage data
-    // ^[[32m INFO^[[0m Coverage stat: time-millis: 1749628484080 instructions: 957/2248 branches: 49/112
-    let coverage_re = Regex::new(
-        r".*Coverage stat: time-millis: (?P<timestamp>\d+) instructions: (?P<instructions_covered>\d+)/(?P<total_instructions>\d+) branches: (?P<branches_covered>\d+)/\d+",
-    )
-    .wrap_err("Failed to compile 'coverage stat' regex")?;
+    let coverage_re =
+        Regex::new(COVERAGE_STAT_PATTERN).wrap_err("Failed to compile 'coverage stat' regex")?;
+    let coverage_percent_re = Regex::new(COVERAGE_PERCENT_PATTERN)
+        .wrap_err("Failed to compile 'coverage percent' regex")?;
 
-    let mut began_at_millis: Option<u64> = None;
+    let mut began_at_millis: Option<u64> = started_at_millis;
+    let mut unmatched_lines = 0;
+    let mut last_timestamp_millis: Option<u64> = None;
+
+    for line in log_content.lines().map(normalize_line) {
+        if verbose_parse
+            && !start_re.is_match(line)
+            && !coverage_re.is_match(line)
+            && !coverage_percent_re.is_match(line)
+        {
+            unmatched_lines += 1;
+            if unmatched_lines <= MAX_UNMATCHED_LINES_LOGGED {
+                debug!("Unmatched log line for {}: {}", contract_id, line);
+            }
+        }
 
-    for line in log_content.lines() {
         if began_at_millis.is_none() {
             if let Some(caps) = start_re.captures(line) {
                 debug!(
                     "Found 'start at' timestamp in log for {}: {}",
                     contract_id, &caps[1]
                 );
-                began_at_millis = Some(caps[1].parse::<u64>().wrap_err_with(|| {
-                    format!("Failed to parse 'start at' timestamp: {}", &caps[1])
-                })?);
+                began_at_millis = Some(timestamp_field.to_millis(
+                    caps[1].parse::<u64>().wrap_err_with(|| {
+                        format!("Failed to parse 'start at' timestamp: {}", &caps[1])
+                    })?,
+                ));
             }
         }
 
         if let Some(current_began_at) = began_at_millis {
-            if let Some(caps) = coverage_re.captures(line) {
-                let instructions_covered = caps["instructions_covered"].parse::<u64>().wrap_err_with(|| {
-                    format!("Failed to parse instructions_covered: {}", &caps["instructions_covered"])
-                })?;
-                let branches_covered = caps["branches_covered"]
+            // Interleaved worker output can put several stats on one physical line
+            let mut samples = Vec::new();
+            for caps in coverage_re.captures_iter(line) {
+                let instructions_covered = caps["instructions_covered"]
                     .parse::<u64>()
-                    .wrap_err_with(|| format!("Failed to parse branches_covered: {}", &caps["branches_covered"]))?;
-                let timestamp_millis: u64 = caps["timestamp"]
-                    .parse::<u64>()
-                    .wrap_err_with(|| format!("Failed to parse timestamp_millis: {}", &caps["timestamp"]))?;
+                    .wrap_err_with(|| {
+                        format!(
+                            "Failed to parse instructions_covered: {}",
+                            &caps["instructions_covered"]
+                        )
+                    })?;
+                let branches_covered =
+                    caps["branches_covered"].parse::<u64>().wrap_err_with(|| {
+                        format!(
+                            "Failed to parse branches_covered: {}",
+                            &caps["branches_covered"]
+                        )
+                    })?;
+                let timestamp_millis =
+                    timestamp_field.to_millis(caps["timestamp"].parse::<u64>().wrap_err_with(
+                        || format!("Failed to parse timestamp_millis: {}", &caps["timestamp"]),
+                    )?);
+
+                let total_instructions =
+                    caps["total_instructions"]
+                        .parse::<u64>()
+                        .wrap_err_with(|| {
+                            format!(
+                                "Failed to parse total_instructions: {}",
+                                &caps["total_instructions"]
+                            )
+                        })?;
 
-                let total_instructions = caps["total_instructions"].parse::<u64>().wrap_err_with(|| {
-                    format!("Failed to parse total_instructions: {}", &caps["total_instructions"])
+                let total_branches = caps["total_branches"].parse::<u64>().wrap_err_with(|| {
+                    format!(
+                        "Failed to parse total_branches: {}",
+                        &caps["total_branches"]
+                    )
                 })?;
 
-                if timestamp_millis >= current_began_at {
-                    let time_taken_millis = timestamp_millis - current_began_at;
-                    entries.push(StatsEntry {
+                samples.push((
+                    timestamp_millis,
+                    StatsEntry {
                         instructions_covered,
                         branches_covered,
                         total_instructions,
+                        time_taken_millis: 0,
+                        total_branches,
+                        coverage_percent: None,
+                        abs_timestamp_millis: Some(timestamp_millis),
+                        execs: caps
+                            .name("execs")
+                            .and_then(|execs| execs.as_str().parse().ok()),
+                    },
+                ));
+            }
+            for caps in coverage_percent_re.captures_iter(line) {
+                let timestamp_millis =
+                    timestamp_field.to_millis(caps["timestamp"].parse::<u64>().wrap_err_with(
+                        || format!("Failed to parse timestamp_millis: {}", &caps["timestamp"]),
+                    )?);
+                let coverage_percent =
+                    caps["coverage_percent"].parse::<f64>().wrap_err_with(|| {
+                        format!(
+                            "Failed to parse coverage_percent: {}",
+                            &caps["coverage_percent"]
+                        )
+                    })?;
+                samples.push((
+                    timestamp_millis,
+                    StatsEntry {
+                        instructions_covered: 0,
+                        branches_covered: 0,
+                        total_instructions: 0,
+                        time_taken_millis: 0,
+                        total_branches: 0,
+                        coverage_percent: Some(coverage_percent),
+                        abs_timestamp_millis: Some(timestamp_millis),
+                        execs: caps
+                            .name("execs")
+                            .and_then(|execs| execs.as_str().parse().ok()),
+                    },
+                ));
+            }
+
+            for (timestamp_millis, sample) in samples {
+                if let Some(last) = last_timestamp_millis {
+                    if timestamp_millis < last {
+                        warn!(
+                            "Non-monotonic coverage timestamp for {}: {} after {}",
+                            contract_id, timestamp_millis, last
+                        );
+                        warnings.push(AnalysisWarning::NonMonotonicCoverage {
+                            contract_id: contract_id.to_string(),
+                            timestamp_millis,
+                            previous_millis: last,
+                        });
+                    } else if timestamp_millis - last > SUSPICIOUS_SAMPLE_GAP_MILLIS {
+                        warn!(
+                            "Suspicious {} ms jump between coverage timestamps for {}: {} after {}",
+                            timestamp_millis - last,
+                            contract_id,
+                            timestamp_millis,
+                            last
+                        );
+                        warnings.push(AnalysisWarning::SuspiciousGap {
+                            contract_id: contract_id.to_string(),
+                            timestamp_millis,
+                            previous_millis: last,
+                        });
+                    }
+                }
+                last_timestamp_millis = Some(timestamp_millis);
+
+                // A clock step mid-run can put a sample before the start time;
+                // drop that sample rather than the whole contract.
+                match timestamp_millis.checked_sub(current_began_at) {
+                    Some(0) if exclude_zero_time => {
+                        debug!(
+                            "Dropping coverage sample at the start time of {}",
+                            contract_id
+                        );
+                    }
+                    Some(time_taken_millis) => entries.push(StatsEntry {
                         time_taken_millis,
-                    });
-                } else {
-                    return Err(eyre!(
-                        "Timestamp {} is before the 'start at' timestamp {} for contract {}",
-                        timestamp_millis,
-                        current_began_at,
-                        contract_id
-                    ));
+                        ..sample
+                    }),
+                    None => {
+                        warn!(
+                            "Skipping sample with timestamp {} before the 'start at' timestamp {} for contract {}",
+                            timestamp_millis, current_began_at, contract_id
+                        );
+                        warnings.push(AnalysisWarning::SampleBeforeStart {
+                            contract_id: contract_id.to_string(),
+                            timestamp_millis,
+                            started_at_millis: current_began_at,
+                        });
+                    }
                 }
             }
         }
@@ -272,22 +1855,147 @@ fn parse_log(log_content: &str, contract_id: &str) -> Result<Vec<StatsEntry>> {
             contract_id,
             log_content.chars().take(300).collect::<String>()
         );
+        warnings.push(AnalysisWarning::MissingStartTimestamp {
+            contract_id: contract_id.to_string(),
+        });
         return Err(eyre!(
             "No 'start at' timestamp found in log for {} despite other stat lines being present.",
             contract_id
         ));
     }
 
-    entries.sort_by_key(|e| e.time_taken_millis);
-    entries.dedup_by_key(|e| e.time_taken_millis);
+    if unmatched_lines > MAX_UNMATCHED_LINES_LOGGED {
+        debug!(
+            "{} more unmatched log lines for {} were not shown",
+            unmatched_lines - MAX_UNMATCHED_LINES_LOGGED,
+            contract_id
+        );
+    }
 
+    dedup_samples(&mut entries, resolution);
     Ok(entries)
 }
 
-fn write_csv(contract_id: &str, entries: &[StatsEntry], output_path_base: &Path) -> Result<()> {
+/// Time of the first `start at` line of a log, in milliseconds
+fn find_start_millis(log_content: &str, timestamp_field: TimestampField) -> Result<Option<u64>> {
+    let start_re = Regex::new(START_PATTERN).wrap_err("Failed to compile 'start at' regex")?;
+    let Some(caps) = log_content.lines().find_map(|line| start_re.captures(line)) else {
+        return Ok(None);
+    };
+    let started_at = caps[1]
+        .parse::<u64>()
+        .wrap_err_with(|| format!("Failed to parse 'start at' timestamp: {}", &caps[1]))?;
+    Ok(Some(timestamp_field.to_millis(started_at)))
+}
+
+/// Sorts samples by time and keeps one per `resolution` bucket
+fn dedup_samples(entries: &mut Vec<StatsEntry>, resolution: TimeResolution) {
+    if resolution == TimeResolution::Raw {
+        entries.sort_by_key(|e| e.time_taken_millis);
+    } else {
+        // Highest coverage first within a bucket, so dedup keeps that sample
+        entries.sort_by(|a, b| {
+            resolution
+                .bucket(a.time_taken_millis)
+                .cmp(&resolution.bucket(b.time_taken_millis))
+                .then(b.instructions_covered.cmp(&a.instructions_covered))
+                .then(
+                    b.coverage_percent
+                        .unwrap_or(0.0)
+                        .total_cmp(&a.coverage_percent.unwrap_or(0.0)),
+                )
+        });
+        entries.dedup_by_key(|e| resolution.bucket(e.time_taken_millis));
+    }
+}
+
+/// Collects the bugs matched by `bug_re` as a cumulative count over time since
+/// the start line. Lines without a start line before them are ignored.
+pub fn parse_bugs(
+    log_content: &str,
+    bug_re: &Regex,
+    timestamp_field: TimestampField,
+) -> Result<Vec<BugEntry>> {
+    let start_re = Regex::new(START_PATTERN).wrap_err("Failed to compile 'start at' regex")?;
+    let mut began_at_millis: Option<u64> = None;
+    // (time since start, bug key), the key is the line itself without a `bug` group
+    let mut matches: Vec<(u64, String)> = Vec::new();
+    for line in log_content.lines().map(normalize_line) {
+        if began_at_millis.is_none() {
+            if let Some(caps) = start_re.captures(line) {
+                began_at_millis = Some(timestamp_field.to_millis(
+                    caps[1].parse::<u64>().wrap_err_with(|| {
+                        format!("Failed to parse 'start at' timestamp: {}", &caps[1])
+                    })?,
+                ));
+            }
+        }
+        let Some(began_at) = began_at_millis else {
+            continue;
+        };
+        for caps in bug_re.captures_iter(line) {
+            let timestamp_millis =
+                timestamp_field.to_millis(caps["timestamp"].parse::<u64>().wrap_err_with(
+                    || format!("Failed to parse bug timestamp: {}", &caps["timestamp"]),
+                )?);
+            let key = caps.name("bug").map_or(line, |bug| bug.as_str());
+            if let Some(time_taken_millis) = timestamp_millis.checked_sub(began_at) {
+                matches.push((time_taken_millis, key.to_string()));
+            }
+        }
+    }
+
+    matches.sort_by_key(|(time_taken_millis, _)| *time_taken_millis);
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    for (time_taken_millis, key) in matches {
+        if seen.insert(key) {
+            entries.push(BugEntry {
+                time_taken_millis,
+                bugs_found: seen.len() as u64,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// Strips a leading UTF-8 BOM and a trailing `\r` left over from CRLF line endings.
+fn normalize_line(line: &str) -> &str {
+    let line = line.strip_prefix('\u{feff}').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// Writes `entries` to `<output_path_base>/<contract_id>.instructions.stats.csv`
+/// With `append`, entries are added to an existing file and the header is only
+/// written if the file is new or empty. Otherwise the file is replaced through
+/// a temporary file, so it is never left half written.
+pub fn write_csv(
+    contract_id: &str,
+    entries: &[StatsEntry],
+    output_path_base: &Path,
+    append: bool,
+) -> Result<()> {
     let csv_path = output_path_base.join(format!("{}.instructions.stats.csv", contract_id));
-    let mut wtr = Writer::from_path(&csv_path)
+    if append {
+        return write_csv_file(&csv_path, entries, true);
+    }
+    let tmp_path = temp_path(&csv_path);
+    write_csv_file(&tmp_path, entries, false)?;
+    rename_into_place(&tmp_path, &csv_path)
+}
+
+fn write_csv_file(csv_path: &Path, entries: &[StatsEntry], append: bool) -> Result<()> {
+    let has_content = append && fs::metadata(csv_path).is_ok_and(|m| m.len() > 0);
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(csv_path)
         .wrap_err_with(|| format!("Failed to create CSV writer for {}", csv_path.display()))?;
+    let mut wtr = WriterBuilder::new()
+        .has_headers(!has_content)
+        .from_writer(file);
     for entry in entries {
         wtr.serialize(entry)
             .wrap_err("Failed to serialize entry to CSV")?;
@@ -295,3 +2003,353 @@ fn write_csv(contract_id: &str, entries: &[StatsEntry], output_path_base: &Path)
     wtr.flush().wrap_err("Failed to flush CSV writer")?;
     Ok(())
 }
+
+/// Writes a single zero sample for every run without entries (`--include-empty`)
+/// and adds it to `all_contract_stats`. Runs whose results failed to write,
+/// and runs appending to an existing CSV, are left alone.
+fn write_empty_csvs(
+    args: &RunArgs,
+    run_ids: &[String],
+    write_failures: &[String],
+    all_contract_stats: &mut HashMap<String, Vec<StatsEntry>>,
+) -> Result<()> {
+    let mut num_written = 0;
+    for run_id in run_ids {
+        if all_contract_stats.contains_key(run_id) || write_failures.contains(run_id) {
+            continue;
+        }
+        let csv_path = args
+            .output_dir
+            .join(format!("{}.instructions.stats.csv", run_id));
+        if args.append && csv_path.exists() {
+            continue;
+        }
+        let entries = vec![StatsEntry {
+            instructions_covered: 0,
+            branches_covered: 0,
+            total_instructions: 0,
+            time_taken_millis: 0,
+            total_branches: 0,
+            coverage_percent: None,
+            abs_timestamp_millis: None,
+            execs: None,
+        }];
+        write_csv(run_id, &entries, &args.output_dir, false)?;
+        all_contract_stats.insert(run_id.clone(), entries);
+        num_written += 1;
+    }
+    if num_written > 0 {
+        info!(
+            "Wrote zero-coverage CSVs for {} contracts without entries",
+            num_written
+        );
+    }
+    Ok(())
+}
+
+/// Writes `entries` to `<output_path_base>/<contract_id>.bugs.csv`, replacing
+/// an existing file
+fn write_bugs_csv(contract_id: &str, entries: &[BugEntry], output_path_base: &Path) -> Result<()> {
+    let csv_path = output_path_base.join(format!("{}.bugs.csv", contract_id));
+    let mut wtr = csv::Writer::from_path(&csv_path)
+        .wrap_err_with(|| format!("Failed to create CSV writer for {}", csv_path.display()))?;
+    for entry in entries {
+        wtr.serialize(entry)
+            .wrap_err("Failed to serialize entry to CSV")?;
+    }
+    wtr.flush().wrap_err("Failed to flush CSV writer")?;
+    Ok(())
+}
+
+fn write_manifest(manifest: &RunManifest, output_path_base: &Path) -> Result<()> {
+    let manifest_path = output_path_base.join("run_manifest.json");
+    let file = fs::File::create(&manifest_path)
+        .wrap_err_with(|| format!("Failed to create manifest file {}", manifest_path.display()))?;
+    serde_json::to_writer_pretty(file, manifest)
+        .wrap_err_with(|| format!("Failed to write manifest file {}", manifest_path.display()))?;
+    Ok(())
+}
+
+/// File name of the warnings list written into the output directory
+pub const WARNINGS_FILE: &str = "warnings.json";
+
+/// Writes all warnings of a run to `<output_path_base>/warnings.json`
+fn write_warnings(warnings: &[AnalysisWarning], output_path_base: &Path) -> Result<()> {
+    let warnings_path = output_path_base.join(WARNINGS_FILE);
+    let file = fs::File::create(&warnings_path)
+        .wrap_err_with(|| format!("Failed to create warnings file {}", warnings_path.display()))?;
+    serde_json::to_writer_pretty(file, warnings)
+        .wrap_err_with(|| format!("Failed to write warnings file {}", warnings_path.display()))?;
+    Ok(())
+}
+
+/// Expands `--seed-template` into fuzzer arguments for `seed`
+fn seed_args(template: &str, seed: u64) -> Vec<String> {
+    template
+        .split_whitespace()
+        .map(|arg| arg.replace("{seed}", &seed.to_string()))
+        .collect()
+}
+
+fn write_meta(meta: &ContractMeta, output_path_base: &Path) -> Result<()> {
+    let meta_path = output_path_base.join(format!("{}.meta.json", meta.contract_id));
+    let file = fs::File::create(&meta_path)
+        .wrap_err_with(|| format!("Failed to create meta file {}", meta_path.display()))?;
+    serde_json::to_writer_pretty(file, meta)
+        .wrap_err_with(|| format!("Failed to write meta file {}", meta_path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "INFO Ityfuzz start at 1000
+INFO Coverage stat: time-millis: 1500 instructions: 5/100 branches: 1/10
+INFO Coverage stat: time-millis: 2000 instructions: 9/100 branches: 2/10
+";
+
+    fn parse(log: &str) -> Vec<StatsEntry> {
+        parse_log(log, "c1", &ParseOptions::default(), &mut Vec::new()).unwrap()
+    }
+
+    #[test]
+    fn parse_log_reads_crlf_lines() {
+        let entries = parse(&LOG.replace('\n', "\r\n"));
+        assert_eq!(entries, parse(LOG));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].time_taken_millis, 1000);
+        assert_eq!(entries[1].total_branches, 10);
+    }
+
+    #[test]
+    fn parse_log_skips_bom() {
+        let entries = parse(&format!("\u{feff}{}", LOG));
+        assert_eq!(entries, parse(LOG));
+        assert_eq!(entries[0].time_taken_millis, 500);
+    }
+
+    #[test]
+    fn parse_log_accepts_tab_separated_fields() {
+        let log = "INFO Ityfuzz start at 1000
+INFO Coverage stat:\ttime-millis:\t1500\tinstructions:\t5/100\tbranches:\t1/10
+INFO Coverage stat:\ttime-millis:\t2000\tinstructions:\t9/100\tbranches:\t2/10
+";
+        assert_eq!(parse(log), parse(LOG));
+    }
+
+    #[test]
+    fn parse_log_accepts_multi_space_separated_fields() {
+        let log = LOG
+            .replace("stat: ", "stat:   ")
+            .replace(" instructions:", "    instructions:");
+        let entries = parse(&log);
+        assert_eq!(entries, parse(LOG));
+        assert_eq!(entries[1].instructions_covered, 9);
+    }
+
+    #[test]
+    fn normalize_dir_strips_trailing_slash_and_dot() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_dir = dir.path().join("b1");
+        fs::create_dir_all(base_dir.join("c1")).unwrap();
+        fs::write(base_dir.join("c1").join("C.bin"), "6080").unwrap();
+
+        for input in [
+            format!("{}/", base_dir.display()),
+            format!("{}/.", base_dir.display()),
+        ] {
+            let normalized = normalize_dir(Path::new(&input));
+            assert_eq!(normalized, fs::canonicalize(&base_dir).unwrap());
+            let contract_dirs = find_contract_dirs(&normalized, 1, false).unwrap();
+            assert_eq!(contract_dirs.len(), 1);
+            let (contract_id, _) =
+                contract_id_of(&normalized, &contract_dirs[0], ContractNameFrom::Dir).unwrap();
+            assert_eq!(contract_id, "c1");
+        }
+    }
+
+    #[test]
+    fn normalize_dir_keeps_missing_dir_name() {
+        assert_eq!(
+            normalize_dir(Path::new("missing/b1/.")),
+            PathBuf::from("missing/b1")
+        );
+        assert_eq!(
+            normalize_dir(Path::new("missing/b1/")),
+            PathBuf::from("missing/b1")
+        );
+    }
+
+    fn write_options_file(dir: &Path, content: &str) -> PathBuf {
+        let path = dir.join("fuzzer.options");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn read_fuzzer_options_file_skips_comments_and_blank_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_options_file(
+            dir.path(),
+            "# shared ityfuzz options\n--concolic\n\n--seed 42  # fixed seed\n",
+        );
+        assert_eq!(
+            read_fuzzer_options_file(&path).unwrap(),
+            vec!["--concolic", "--seed", "42"]
+        );
+    }
+
+    #[test]
+    fn read_fuzzer_options_file_splits_quoted_words() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_options_file(
+            dir.path(),
+            "--onchain-url 'http://localhost:8545'\n--flashloan-name \"two words\"\n",
+        );
+        assert_eq!(
+            read_fuzzer_options_file(&path).unwrap(),
+            vec![
+                "--onchain-url",
+                "http://localhost:8545",
+                "--flashloan-name",
+                "two words"
+            ]
+        );
+    }
+
+    #[test]
+    fn read_fuzzer_options_file_reports_unterminated_quote_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_options_file(dir.path(), "--concolic\n--seed '42\n");
+        let error = read_fuzzer_options_file(&path).unwrap_err().to_string();
+        assert!(error.contains(":2:"), "{}", error);
+    }
+
+    #[test]
+    fn fuzzer_options_file_must_not_set_reserved_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_options_file(dir.path(), "--concolic\n--target=./c1\n");
+        let options = read_fuzzer_options_file(&path).unwrap();
+        assert!(check_fuzzer_options(&options, "-t", "--fuzzer-options-file").is_err());
+        assert!(check_fuzzer_options(&options, "--contract", "--fuzzer-options-file").is_ok());
+    }
+
+    #[test]
+    fn read_fuzzer_options_file_keeps_quoted_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_options_file(
+            dir.path(),
+            "--onchain-url 'http://host/#x' # RPC endpoint\n--flag a#b\n",
+        );
+        assert_eq!(
+            read_fuzzer_options_file(&path).unwrap(),
+            vec!["--onchain-url", "http://host/#x", "--flag", "a#b"]
+        );
+    }
+
+    #[test]
+    fn merge_fuzzer_options_puts_file_options_after_cli_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_options_file(dir.path(), "--seed 42\n--concolic\n");
+        let cli_options = vec!["evm".to_string(), "--run-forever".to_string()];
+        assert_eq!(
+            merge_fuzzer_options(&cli_options, Some(&path), "-t").unwrap(),
+            vec!["evm", "--run-forever", "--seed", "42", "--concolic"]
+        );
+        assert_eq!(
+            merge_fuzzer_options(&cli_options, None, "-t").unwrap(),
+            cli_options
+        );
+    }
+
+    #[test]
+    fn classify_run_tells_hung_from_timed_out() {
+        let hang_limit = Duration::from_secs(3);
+        let timed_out =
+            |since_coverage| classify_run(ExitKind::Timeout, false, since_coverage, hang_limit);
+        assert_eq!(timed_out(None), ExitKind::Hung);
+        assert_eq!(timed_out(Some(Duration::from_secs(14))), ExitKind::Hung);
+        assert_eq!(timed_out(Some(Duration::from_secs(3))), ExitKind::Hung);
+        assert_eq!(timed_out(Some(Duration::from_secs(1))), ExitKind::Timeout);
+    }
+
+    #[test]
+    fn classify_run_only_refines_timeouts() {
+        let hang_limit = Duration::from_secs(3);
+        for exit_kind in [
+            ExitKind::Success,
+            ExitKind::AcceptedExitCode,
+            ExitKind::Failure,
+        ] {
+            assert_eq!(classify_run(exit_kind, false, None, hang_limit), exit_kind);
+        }
+        assert_eq!(
+            classify_run(ExitKind::Timeout, true, None, hang_limit),
+            ExitKind::PlateauStopped
+        );
+    }
+
+    #[test]
+    fn check_fuzzer_options_points_work_dir_flags_to_workdir_root() {
+        let options = vec!["-w".to_string(), "/tmp/work".to_string()];
+        let error = check_fuzzer_options(&options, "-t", "--fuzzer-options")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("under --workdir-root"), "{}", error);
+    }
+
+    #[test]
+    fn spawn_with_backoff_does_not_retry_a_missing_binary() {
+        let mut command = Command::new("/nonexistent/fuzzer");
+        let error = spawn_with_backoff(&mut command, "/nonexistent/fuzzer").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to start program /nonexistent/fuzzer"
+        );
+    }
+
+    #[test]
+    fn retry_spawn_retries_would_block() {
+        let mut calls = 0;
+        let spawned = retry_spawn("fuzzer", Duration::ZERO, || {
+            calls += 1;
+            if calls < 3 {
+                Err(std::io::ErrorKind::WouldBlock.into())
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(spawned.unwrap(), 3);
+    }
+
+    #[test]
+    fn retry_spawn_gives_up_after_all_attempts() {
+        let mut calls = 0;
+        let error = retry_spawn::<()>("fuzzer", Duration::ZERO, || {
+            calls += 1;
+            Err(std::io::ErrorKind::WouldBlock.into())
+        })
+        .unwrap_err();
+        assert_eq!(calls, SPAWN_ATTEMPTS);
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Failed to start program fuzzer after {} attempts",
+                SPAWN_ATTEMPTS
+            )
+        );
+    }
+
+    #[test]
+    fn retry_spawn_reports_permission_denied_once() {
+        let mut calls = 0;
+        let error = retry_spawn::<()>("fuzzer", Duration::ZERO, || {
+            calls += 1;
+            Err(std::io::ErrorKind::PermissionDenied.into())
+        })
+        .unwrap_err();
+        assert_eq!(calls, 1);
+        assert_eq!(error.to_string(), "Failed to start program fuzzer");
+    }
+}
@@ -0,0 +1,122 @@
+use crate::plot::split_run_id;
+use crate::types::StatsEntry;
+use eyre::{Result, WrapErr};
+use rusqlite::{Connection, OpenFlags, params};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::info;
+
+const CREATE_STATS_TABLE: &str = "CREATE TABLE IF NOT EXISTS stats (
+    contract_id TEXT NOT NULL,
+    run_index INTEGER NOT NULL,
+    time_ms INTEGER NOT NULL,
+    instructions INTEGER NOT NULL,
+    branches INTEGER NOT NULL,
+    total_instructions INTEGER NOT NULL,
+    total_branches INTEGER NOT NULL,
+    coverage_percent REAL,
+    abs_timestamp_ms INTEGER,
+    execs INTEGER
+)";
+
+/// Stores all entries in the `stats` table of `db_path` in one transaction.
+/// Rows of a contract run already in the database are replaced. `run_index`
+/// is the `--repeat` repetition, 0 for single runs.
+pub fn write_stats_to_sqlite(
+    db_path: &Path,
+    all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
+) -> Result<()> {
+    let mut conn = Connection::open(db_path)
+        .wrap_err_with(|| format!("Failed to open SQLite database {}", db_path.display()))?;
+    conn.execute(CREATE_STATS_TABLE, [])
+        .wrap_err("Failed to create stats table")?;
+
+    let tx = conn.transaction().wrap_err("Failed to start transaction")?;
+    {
+        let mut delete = tx
+            .prepare("DELETE FROM stats WHERE contract_id = ?1 AND run_index = ?2")
+            .wrap_err("Failed to prepare delete statement")?;
+        let mut insert = tx
+            .prepare(
+                "INSERT INTO stats (contract_id, run_index, time_ms, instructions, branches, total_instructions, total_branches, coverage_percent, abs_timestamp_ms, execs)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )
+            .wrap_err("Failed to prepare insert statement")?;
+        for (run_id, entries) in all_contract_stats {
+            let (contract_id, run_index) = split_run_id(run_id);
+            delete
+                .execute(params![contract_id, run_index])
+                .wrap_err_with(|| format!("Failed to delete old rows of {}", run_id))?;
+            for entry in entries {
+                insert
+                    .execute(params![
+                        contract_id,
+                        run_index,
+                        entry.time_taken_millis as i64,
+                        entry.instructions_covered as i64,
+                        entry.branches_covered as i64,
+                        entry.total_instructions as i64,
+                        entry.total_branches as i64,
+                        entry.coverage_percent,
+                        entry.abs_timestamp_millis.map(|millis| millis as i64),
+                        entry.execs.map(|execs| execs as i64),
+                    ])
+                    .wrap_err_with(|| format!("Failed to insert stats of {}", run_id))?;
+            }
+        }
+    }
+    tx.commit().wrap_err("Failed to commit stats transaction")?;
+    info!(
+        "Stored stats of {} runs in {}",
+        all_contract_stats.len(),
+        db_path.display()
+    );
+    Ok(())
+}
+
+/// Loads the `stats` table keyed by run id, the same keys `load_contract_stats`
+/// derives from CSV file names
+pub fn load_stats_from_sqlite(db_path: &Path) -> Result<HashMap<String, Vec<StatsEntry>>> {
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .wrap_err_with(|| format!("Failed to open SQLite database {}", db_path.display()))?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT contract_id, run_index, time_ms, instructions, branches, total_instructions, total_branches, coverage_percent, abs_timestamp_ms, execs
+             FROM stats ORDER BY contract_id, run_index, time_ms",
+        )
+        .wrap_err("Failed to prepare stats query")?;
+    let rows = stmt
+        .query_map([], |row| {
+            let contract_id: String = row.get(0)?;
+            let run_index: u32 = row.get(1)?;
+            let entry = StatsEntry {
+                time_taken_millis: row.get::<_, i64>(2)? as u64,
+                instructions_covered: row.get::<_, i64>(3)? as u64,
+                branches_covered: row.get::<_, i64>(4)? as u64,
+                total_instructions: row.get::<_, i64>(5)? as u64,
+                total_branches: row.get::<_, i64>(6)? as u64,
+                coverage_percent: row.get(7)?,
+                abs_timestamp_millis: row.get::<_, Option<i64>>(8)?.map(|millis| millis as u64),
+                execs: row.get::<_, Option<i64>>(9)?.map(|execs| execs as u64),
+            };
+            Ok((contract_id, run_index, entry))
+        })
+        .wrap_err("Failed to query stats")?;
+
+    let mut all_contract_stats: HashMap<String, Vec<StatsEntry>> = HashMap::new();
+    for row in rows {
+        let (contract_id, run_index, entry) = row.wrap_err("Failed to read stats row")?;
+        let run_id = if run_index == 0 {
+            contract_id
+        } else {
+            format!("{}.run{}", contract_id, run_index)
+        };
+        all_contract_stats.entry(run_id).or_default().push(entry);
+    }
+    info!(
+        "Loaded stats of {} runs from {}",
+        all_contract_stats.len(),
+        db_path.display()
+    );
+    Ok(all_contract_stats)
+}
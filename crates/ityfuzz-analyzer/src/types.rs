@@ -1,6 +1,8 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use eyre::eyre;
 // Added Reader
 use serde::{Deserialize, Serialize}; // Added Deserialize
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
@@ -8,26 +10,73 @@ use std::path::PathBuf;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Only print errors on the console, without progress output; the log
+    /// file keeps its full `LOG_LEVEL` detail
+    #[arg(long, global = true)]
+    pub quiet: bool,
+
+    /// Write debug messages to the log file, overriding `LOG_LEVEL`
+    #[arg(long, global = true)]
+    pub verbose: bool,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Run the fuzzer, analyze output, write CSVs, and plot results
-    Run(RunArgs),
+    Run(Box<RunArgs>),
     /// Plot results from existing CSV data in the output directory
-    Plot(PlotArgs),
+    Plot(Box<PlotArgs>),
+    /// Remove generated CSVs, plots and work directories
+    Clean(CleanArgs),
+    /// Compare final per-contract coverage between two output directories
+    Diff(DiffArgs),
+}
+
+impl Cli {
+    /// Rejects flag combinations clap cannot express, before any work starts
+    pub fn validate(&self) -> eyre::Result<()> {
+        match &self.command {
+            Commands::Run(args) => args.validate(),
+            Commands::Plot(args) => args.validate(),
+            Commands::Clean(_) | Commands::Diff(_) => Ok(()),
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
 pub struct RunArgs {
-    /// Number of current jobs to run
-    #[arg(short, long, value_name = "NUM", default_value_t = 1)]
-    pub jobs: usize,
+    /// Number of concurrent jobs to run; defaults to the number of CPUs and
+    /// never exceeds the number of runs
+    #[arg(short, long, value_name = "NUM", value_parser = parse_jobs)]
+    pub jobs: Option<usize>,
+
+    /// Number of threads each fuzzer process uses itself; `--jobs` is capped so
+    /// that jobs * fuzzer threads does not exceed the available cores
+    #[arg(long, value_name = "NUM", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub fuzzer_threads: u32,
 
-    /// Path to the fuzzer executable
+    /// Path to the fuzzer executable. A command with arguments, such as a
+    /// wrapper script, is split into words like a shell would
     #[arg(short, long, value_name = "FILE", default_value = "ityfuzz")]
     pub fuzzer_path: String,
 
+    /// Working directory of the fuzzer process, for fuzzers that resolve
+    /// relative paths such as their config against it. A relative
+    /// `--fuzzer-path` is looked up from this directory
+    #[arg(long, value_name = "DIR")]
+    pub fuzzer_cwd: Option<PathBuf>,
+
+    /// Run `--fuzzer-path` with `sh -c`, so it may use shell syntax; the
+    /// per-contract arguments are passed to it as `"$@"`
+    #[arg(long)]
+    pub fuzzer_shell: bool,
+
+    /// Flag that makes the fuzzer print its version, run once before fuzzing.
+    /// Without usable output the fuzzer binary's mtime and hash are recorded
+    #[arg(long, value_name = "FLAG", default_value = "--version")]
+    pub fuzzer_version_flag: String,
+
     /// Additional arguments to be added before the `-t <target-contract-folder>/*` argument for ityfuzz
     #[arg(long,
           default_values_t = vec![
@@ -37,10 +86,58 @@ pub struct RunArgs {
           value_name = "ARGS")]
     pub fuzzer_options: Vec<String>,
 
+    /// File of further fuzzer options, split into words like a shell would;
+    /// `#` starts a comment. They are passed in file order after all
+    /// `--fuzzer-options`, so the subcommand (e.g. `evm`) stays first
+    #[arg(long, value_name = "FILE")]
+    pub fuzzer_options_file: Option<PathBuf>,
+
     /// Base directory containing benchmark contract directories (e.g., b1)
     #[arg(short, long, value_name = "DIR")]
     pub benchmark_base_dir: PathBuf,
 
+    /// Directory level of the contract directories below the base directory.
+    /// Intermediate directories form a group label that prefixes the contract id
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub depth: u32,
+
+    /// Descend until directories that contain files, instead of a fixed `--depth`
+    #[arg(long, conflicts_with = "depth")]
+    pub recursive: bool,
+
+    /// Print the contract directories that would be fuzzed, in processing
+    /// order, and exit without running the fuzzer
+    #[arg(long)]
+    pub list_contracts: bool,
+
+    /// Only fuzz a random fraction (e.g. 0.05) of the contract directories,
+    /// for quick smoke runs. The choice is seeded by `--seed`
+    #[arg(long, value_name = "FRACTION", value_parser = parse_fraction, conflicts_with = "sample_n")]
+    pub sample: Option<f64>,
+
+    /// Only fuzz N randomly chosen contract directories, like `--sample`
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub sample_n: Option<u64>,
+
+    /// Only pass files with these extensions (comma-separated, e.g. `bin,abi`)
+    /// to the fuzzer. Subdirectories of a contract directory are always excluded
+    #[arg(long, value_name = "EXTS", value_delimiter = ',')]
+    pub target_ext: Vec<String>,
+
+    /// Pass each target file with its own target flag instead of one glob of
+    /// the contract directory, for fuzzers that do not expand globs
+    #[arg(long)]
+    pub target_flag_repeat: bool,
+
+    /// Fuzzer flag the targets are passed with
+    #[arg(
+        long,
+        value_name = "FLAG",
+        default_value = "-t",
+        allow_hyphen_values = true
+    )]
+    pub target_flag: String,
+
     /// Output directory for CSV files and the plot
     #[arg(short, long, value_name = "DIR", default_value = "analysis_output")]
     pub output_dir: PathBuf,
@@ -48,21 +145,936 @@ pub struct RunArgs {
     /// Timeout in seconds for running the fuzzer on each contract
     #[arg(long, value_name = "SECONDS", default_value_t = 15)]
     pub fuzz_timeout_seconds: u64,
+
+    /// Scale each contract's timeout with its size: `--fuzz-timeout-seconds`
+    /// plus this many seconds per KiB of bytecode in its `.bin` file
+    #[arg(long, value_name = "SECONDS_PER_KIB")]
+    pub timeout_scale: Option<f64>,
+
+    /// Upper bound of the timeouts computed with `--timeout-scale`
+    #[arg(long, value_name = "SECONDS", default_value_t = 3600)]
+    pub max_timeout_seconds: u64,
+
+    /// Exit codes (comma-separated) treated as normal completion besides 0 and
+    /// the timeout code 124, e.g. for fuzzers that exit nonzero on finding a bug
+    #[arg(long, value_name = "CODES", value_delimiter = ',')]
+    pub success_exit_codes: Vec<i32>,
+
+    /// Log (at debug level, see LOG_LEVEL) the first lines of each fuzzer log
+    /// that matched neither the start nor the coverage regex
+    #[arg(long)]
+    pub verbose_parse: bool,
+
+    /// Granularity at which coverage samples are deduplicated; each bucket
+    /// keeps its highest-coverage sample
+    #[arg(long, value_enum, default_value_t = TimeResolution::Ms)]
+    pub time_resolution: TimeResolution,
+
+    /// Drop coverage samples logged at the start time itself, which some
+    /// fuzzers print before executing anything
+    #[arg(long)]
+    pub exclude_zero_time: bool,
+
+    /// Unit of the timestamps in the fuzzer log, which differs between ityfuzz
+    /// builds; they are converted to milliseconds for the CSVs
+    #[arg(long, value_enum, default_value_t = TimestampField::Millis)]
+    pub timestamp_field: TimestampField,
+
+    /// How to report progress: an animated bar, plain text lines for CI logs, or nothing
+    #[arg(long, value_enum, default_value_t = ProgressMode::Auto)]
+    pub progress: ProgressMode,
+
+    /// What to do when two contract directories resolve to the same contract id
+    #[arg(long, value_enum, default_value_t = CollisionPolicy::Rename)]
+    pub on_collision: CollisionPolicy,
+
+    /// Where contract ids come from: the directory name, or the stem of the
+    /// compiled `.abi`/`.bin` file in it, falling back to the directory name
+    #[arg(long, value_enum, default_value_t = ContractNameFrom::Dir)]
+    pub contract_name_from: ContractNameFrom,
+
+    /// Directory the per-contract fuzzer work directories are created in, as
+    /// `<root>/<time>/<contract>`
+    #[arg(long, value_name = "DIR", default_value = WORK_DIRS_ROOT)]
+    pub workdir_root: PathBuf,
+
+    /// When to remove a contract's work directory after its run
+    #[arg(long, value_enum, default_value_t = WorkdirCleanup::Keep)]
+    pub workdir_cleanup: WorkdirCleanup,
+
+    #[command(flatten)]
+    pub limits: ResourceLimits,
+
+    /// Stop a contract early once its instruction coverage has not increased for this many seconds
+    #[arg(long)]
+    pub plateau_seconds: Option<u64>,
+
+    /// A fuzzer killed by the timeout counts as hung rather than timed out if
+    /// it printed no coverage line in its last N seconds; must be below
+    /// `--fuzz-timeout-seconds` [default: a quarter of the contract's timeout]
+    #[arg(long, value_name = "SECONDS")]
+    pub hang_seconds: Option<u64>,
+
+    /// Seed passed to the fuzzer; repetition `k` (0-based) uses `seed + k`
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Fuzzer arguments carrying the seed, split on whitespace; `{seed}` is
+    /// replaced by the seed value
+    #[arg(long, value_name = "TEMPLATE", default_value = "--seed {seed}")]
+    pub seed_template: String,
+
+    /// Log file the fuzzer writes its coverage to, read after it exits in
+    /// addition to stdout. `{workdir}` and `{contract}` are replaced by the
+    /// run's work directory and id, e.g. `{workdir}/fuzz.log`
+    #[arg(long, value_name = "TEMPLATE")]
+    pub log_file_pattern: Option<String>,
+
+    /// Number of times each contract is fuzzed. With more than one repetition,
+    /// results are stored per run as `<contract>.run<k>`
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub repeat: u32,
+
+    /// Title prefix of the generated plots and summary CSVs; defaults to the
+    /// output directory name
+    #[arg(long, value_name = "TITLE")]
+    pub plot_title: Option<String>,
+
+    /// Also write a self-contained `report.html` with the plots, a per-contract
+    /// summary table and the run manifest
+    #[arg(long)]
+    pub html_report: bool,
+
+    /// Draw the `--fuzz-timeout-seconds` boundary as a vertical line on the
+    /// aggregate plot, to tell runs cut off by the timeout from finished ones
+    #[arg(long)]
+    pub show_timeout: bool,
+
+    /// Only write the per-contract CSVs; skip the aggregate plot and its
+    /// summary CSVs, e.g. on headless servers
+    #[arg(long)]
+    pub no_plot: bool,
+
+    /// Fuzzer option swept over several values, as FLAG=V1,V2,... (repeatable).
+    /// Each contract is run once per combination of all matrix values and the
+    /// run is stored as `<contract>__<combo>`, e.g. `c1__power-schedule=fast`
+    #[arg(long = "matrix", value_name = "FLAG=V1,V2", value_parser = parse_matrix_axis)]
+    pub matrix: Vec<MatrixAxis>,
+
+    /// File with one `--matrix` FLAG=V1,V2,... entry per line; `#` starts a comment
+    #[arg(long, value_name = "FILE")]
+    pub option_matrix: Option<PathBuf>,
+
+    /// Environment variable set for the fuzzer, as KEY=VALUE (repeatable)
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_env_var)]
+    pub env: Vec<(String, String)>,
+
+    /// File piped to the fuzzer's stdin, e.g. a seed corpus manifest. Without
+    /// it the fuzzer gets an empty stdin
+    #[arg(long, value_name = "FILE")]
+    pub fuzzer_stdin: Option<PathBuf>,
+
+    /// Regex matched against the fuzzer's stderr; its `execs` group is shown
+    /// in the progress bar as the current execs/sec
+    #[arg(
+        long,
+        value_name = "REGEX",
+        default_value = r"exec/sec:\s*(?P<execs>[0-9.]+[kKmM]?)"
+    )]
+    pub execs_pattern: String,
+
+    /// Regex matching a bug/objective line of the fuzzer log; its `timestamp`
+    /// group holds a timestamp like the coverage lines. With a `bug` group,
+    /// each distinct value is counted once. Matches go to `<contract>.bugs.csv`
+    #[arg(
+        long,
+        value_name = "REGEX",
+        default_value = r"(?:Found bug|[Oo]bjective).*?time-(?:millis|nanos|seconds):\s*(?P<timestamp>\d+)"
+    )]
+    pub bug_pattern: String,
+
+    /// Also store all stats in this SQLite database (table `stats`)
+    #[arg(long, value_name = "FILE")]
+    pub sqlite: Option<PathBuf>,
+
+    /// Also write each contract's final coverage as Prometheus gauges to this
+    /// file, e.g. for the node_exporter textfile collector
+    #[arg(long, value_name = "FILE")]
+    pub prometheus: Option<PathBuf>,
+
+    /// Stop the whole run when a contract's CSV cannot be written instead of
+    /// logging the failure and continuing with the other contracts
+    #[arg(long)]
+    pub abort_on_write_error: bool,
+
+    /// Append samples to existing per-contract CSVs instead of overwriting them.
+    /// Times are relative to each run's start, so appended segments only line
+    /// up if they continue the same time base (e.g. a resumed campaign).
+    #[arg(long)]
+    pub append: bool,
+
+    /// While a contract runs, rewrite its CSV with the samples parsed from its
+    /// stdout so far after this many new coverage samples, so a crash or kill
+    /// keeps the partial data
+    #[arg(long, value_name = "SAMPLES", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "append")]
+    pub flush_every_samples: Option<u64>,
+
+    /// Like `--flush-every-samples`, but rewrite the CSV at most this many
+    /// seconds after new output arrived
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "append")]
+    pub flush_every_seconds: Option<u64>,
+
+    /// Write a single zero-coverage sample for contracts that yielded no
+    /// entries, so that every contract has a CSV and a summary row
+    #[arg(long)]
+    pub include_empty: bool,
+}
+
+impl RunArgs {
+    pub fn validate(&self) -> eyre::Result<()> {
+        if let Some(fuzzer_cwd) = &self.fuzzer_cwd {
+            if !fuzzer_cwd.is_dir() {
+                return Err(eyre!(
+                    "Fuzzer working directory {} does not exist",
+                    fuzzer_cwd.display()
+                ));
+            }
+        }
+        if let Some(stdin_path) = &self.fuzzer_stdin {
+            if !stdin_path.is_file() {
+                return Err(eyre!(
+                    "Fuzzer stdin file {} does not exist",
+                    stdin_path.display()
+                ));
+            }
+        }
+        if let Some(path) = &self.fuzzer_options_file {
+            if !path.is_file() {
+                return Err(eyre!(
+                    "Fuzzer options file {} does not exist",
+                    path.display()
+                ));
+            }
+        }
+        if let Some(path) = &self.option_matrix {
+            if !path.is_file() {
+                return Err(eyre!(
+                    "Option matrix file {} does not exist",
+                    path.display()
+                ));
+            }
+        }
+        if let Some(hang_seconds) = self.hang_seconds {
+            if hang_seconds >= self.fuzz_timeout_seconds {
+                return Err(eyre!(
+                    "--hang-seconds {} must be below --fuzz-timeout-seconds {}",
+                    hang_seconds,
+                    self.fuzz_timeout_seconds
+                ));
+            }
+        }
+        if let Some(scale) = self.timeout_scale {
+            if !scale.is_finite() || scale < 0.0 {
+                return Err(eyre!(
+                    "--timeout-scale must be a non-negative number, got {}",
+                    scale
+                ));
+            }
+            if self.max_timeout_seconds < self.fuzz_timeout_seconds {
+                return Err(eyre!(
+                    "--max-timeout-seconds {} is below --fuzz-timeout-seconds {}",
+                    self.max_timeout_seconds,
+                    self.fuzz_timeout_seconds
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_fraction(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(fraction) if fraction > 0.0 && fraction <= 1.0 => Ok(fraction),
+        _ => Err(format!("expected a fraction in (0, 1], got '{}'", s)),
+    }
+}
+
+fn parse_jobs(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("must be at least 1; omit --jobs to use one job per CPU".to_string()),
+        Ok(jobs) => Ok(jobs),
+        Err(_) => Err(format!("expected a positive number, got '{}'", s)),
+    }
+}
+
+/// Number of CPUs a `cpu_set_t` holds (glibc's `CPU_SETSIZE`)
+const CPU_SETSIZE: usize = 1024;
+
+/// Rejects CPU ids that `CPU_SET` cannot hold; it panics on them in the
+/// forked fuzzer process
+fn parse_cpu_id(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(cpu) if cpu < CPU_SETSIZE => Ok(cpu),
+        Ok(_) => Err(format!(
+            "CPU ids must be below {}, got '{}'",
+            CPU_SETSIZE, s
+        )),
+        Err(_) => Err(format!("expected a CPU id, got '{}'", s)),
+    }
+}
+
+fn parse_env_var(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", s)),
+    }
+}
+
+/// Fuzzer option swept by `--matrix`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixAxis {
+    /// Option name, passed to the fuzzer as `--<flag>` unless it starts with `-`
+    pub flag: String,
+    pub values: Vec<String>,
+}
+
+impl MatrixAxis {
+    /// The option as passed to the fuzzer
+    pub fn fuzzer_flag(&self) -> String {
+        if self.flag.starts_with('-') {
+            self.flag.clone()
+        } else {
+            format!("--{}", self.flag)
+        }
+    }
+}
+
+/// Parses a FLAG=V1,V2,... matrix axis. Values end up in file names, so they
+/// must not contain a path separator.
+pub fn parse_matrix_axis(s: &str) -> Result<MatrixAxis, String> {
+    let Some((flag, values)) = s.split_once('=') else {
+        return Err(format!("expected FLAG=V1,V2,..., got '{}'", s));
+    };
+    let flag = flag.trim();
+    if flag.trim_start_matches('-').is_empty() {
+        return Err(format!("missing option name in '{}'", s));
+    }
+    let values: Vec<String> = values.split(',').map(|v| v.trim().to_string()).collect();
+    if values.iter().any(|v| v.is_empty() || v.contains('/')) {
+        return Err(format!(
+            "matrix values must be non-empty and contain no '/', got '{}'",
+            s
+        ));
+    }
+    Ok(MatrixAxis {
+        flag: flag.to_string(),
+        values,
+    })
+}
+
+/// Separates the benchmark group from the contract name in contract ids
+pub const GROUP_SEPARATOR: &str = "__";
+
+/// Default root of the fuzzer work directories created by the `run` command
+pub const WORK_DIRS_ROOT: &str = ".work-dirs";
+
+/// Per-process resource limits applied to each fuzzer before exec.
+/// Unix-only; ignored on other platforms.
+#[derive(Args, Debug, Clone, Default)]
+pub struct ResourceLimits {
+    /// Cap the fuzzer's address space (RLIMIT_AS) in megabytes
+    #[arg(long)]
+    pub mem_limit_mb: Option<u64>,
+
+    /// Pin the fuzzer to these CPU cores, comma-separated (Linux only)
+    #[arg(long, value_name = "CPUS", value_delimiter = ',', value_parser = parse_cpu_id)]
+    pub cpu_affinity: Vec<usize>,
+}
+
+/// Handling of contract directories that share the same contract id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CollisionPolicy {
+    /// Abort the run, listing the colliding paths
+    Error,
+    /// Prefix colliding ids with their parent directory name, the contract
+    /// directory itself for ids taken from an artifact
+    Rename,
+    /// Keep the last directory's results, overwriting earlier ones
+    Overwrite,
+}
+
+/// When the fuzzer work directory of a contract is removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WorkdirCleanup {
+    /// Never remove work directories
+    Keep,
+    /// Remove a contract's work directory once its CSV is written
+    OnSuccess,
+    /// Remove a contract's work directory after its run, even if it failed
+    Always,
+}
+
+/// Source of the contract id of a contract directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ContractNameFrom {
+    /// Name of the contract directory
+    Dir,
+    /// Stem of the `.abi` file, the Solidity contract name
+    Abi,
+    /// Stem of the `.bin` file
+    Bin,
+}
+
+impl ContractNameFrom {
+    /// Extension of the artifact the name is taken from
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            ContractNameFrom::Dir => None,
+            ContractNameFrom::Abi => Some("abi"),
+            ContractNameFrom::Bin => Some("bin"),
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
 pub struct PlotArgs {
-    /// Directory containing the CSV data files and where the plot will be saved
+    /// Directory containing the CSV data files and where the plot will be saved.
+    /// A `<dir>.tar.gz` archive of it is read in place and plotted into `<dir>`
     #[arg(short, long, value_name = "DIR", default_value = "analysis_output")]
     pub output_dir: PathBuf,
+
+    /// Maximum number of points drawn per series; longer series are downsampled
+    /// (the written CSVs keep full resolution). Use 0 to disable downsampling
+    #[arg(long, value_name = "N", default_value_t = 5000)]
+    pub max_points: usize,
+
+    /// Leave contracts with fewer than N samples out of the plots, where they
+    /// only add near-vertical lines; their CSVs are kept
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub min_samples: usize,
+
+    /// Metric to plot against time
+    #[arg(long, value_enum, default_value_t = Metric::Instructions)]
+    pub metric: Metric,
+
+    /// Shape of the aggregate plot
+    #[arg(long, value_enum, default_value_t = PlotKind::Line)]
+    pub plot_kind: PlotKind,
+
+    /// Quantity on the x axis of the aggregate plot. `execs` needs coverage
+    /// lines with an `execs:` count and writes `<title>_execs_*` files
+    #[arg(long, value_enum, default_value_t = XAxis::Time)]
+    pub x_axis: XAxis,
+
+    /// Stroke width of the plotted lines in pixels, e.g. 3 for projectors
+    #[arg(long, value_name = "PIXELS", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub line_width: u32,
+
+    /// Also draw a marker at every plotted sample
+    #[arg(long)]
+    pub markers: bool,
+
+    /// Maximum number of bands of a stacked plot, including the `other` band
+    /// that sums up the contracts with the least coverage
+    #[arg(long, value_name = "N", default_value_t = 9, value_parser = clap::value_parser!(u32).range(2..))]
+    pub max_bands: u32,
+
+    /// Moving-average window (number of samples) applied to the plotted series
+    #[arg(long, value_name = "N")]
+    pub smooth: Option<usize>,
+
+    /// Unit of the y axis; `auto` picks one based on the largest plotted value
+    #[arg(long, value_enum, default_value_t = YUnit::Auto)]
+    pub y_unit: YUnit,
+
+    /// Draw one small branch-coverage-percent chart per contract instead of
+    /// the aggregate plot, paginated into several PNGs for many contracts
+    #[arg(long)]
+    pub small_multiples: bool,
+
+    /// How repeated runs of a contract (`<contract>.run<k>`) are combined at
+    /// each timestamp
+    #[arg(long, value_enum, default_value_t = Aggregate::Mean)]
+    pub aggregate: Aggregate,
+
+    /// How the coverage of different contracts is combined at each timestamp
+    #[arg(long, value_enum, default_value_t = AggregateMode::Sum)]
+    pub aggregate_mode: AggregateMode,
+
+    /// Where each contract's time axis starts
+    #[arg(long, value_enum, default_value_t = Align::Zero)]
+    pub align: Align,
+
+    /// Also write `<title>_aggregate_debug.csv` with each contract's latest
+    /// covered instructions at every aggregated timestamp
+    #[arg(long)]
+    pub debug_aggregate: bool,
+
+    /// Only recompute the overall stats CSVs from the per-contract CSVs and
+    /// skip drawing the chart
+    #[arg(long, conflicts_with = "small_multiples")]
+    pub csv_only: bool,
+
+    /// Skip any CSV whose read takes longer than this, e.g. on a stale network mount
+    #[arg(long, value_name = "SECONDS")]
+    pub read_timeout_seconds: Option<u64>,
+
+    /// Read the stats from this SQLite database instead of the CSV files
+    #[arg(long, value_name = "FILE")]
+    pub sqlite: Option<PathBuf>,
+
+    /// Also write each contract's final coverage as Prometheus gauges to this
+    /// file, e.g. for the node_exporter textfile collector
+    #[arg(long, value_name = "FILE")]
+    pub prometheus: Option<PathBuf>,
+
+    /// File of `contract_id,final_coverage` lines drawn as a reference line;
+    /// contracts without a baseline value are skipped
+    #[arg(long, value_name = "FILE")]
+    pub baseline: Option<PathBuf>,
+
+    /// Title prefix of the generated plots and summary CSVs; defaults to the
+    /// output directory name
+    #[arg(long, value_name = "TITLE")]
+    pub plot_title: Option<String>,
+
+    /// Also write a self-contained `report.html` with the plots, a per-contract
+    /// summary table and the run manifest
+    #[arg(long)]
+    pub html_report: bool,
+
+    /// Draw the fuzzing timeout recorded in `run_manifest.json` as a vertical
+    /// line on the aggregate plot
+    #[arg(long)]
+    pub show_timeout: bool,
+
+    /// Re-read the CSVs and regenerate the plot every INTERVAL seconds until
+    /// interrupted, to follow a running campaign
+    #[arg(long, value_name = "INTERVAL", value_parser = clap::value_parser!(u64).range(1..))]
+    pub follow: Option<u64>,
+
+    /// Replace the caption of the aggregate plot
+    #[arg(long, value_name = "TEXT")]
+    pub caption: Option<String>,
+
+    /// Replace the x axis description of the aggregate plot
+    #[arg(long, value_name = "TEXT")]
+    pub x_label: Option<String>,
+
+    /// Replace the y axis description of the aggregate plot
+    #[arg(long, value_name = "TEXT")]
+    pub y_label: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl PlotArgs {
+    pub fn validate(&self) -> eyre::Result<()> {
+        if self.plot_kind == PlotKind::Stacked {
+            if self.metric != Metric::Instructions {
+                return Err(eyre!(
+                    "--plot-kind stacked only supports --metric instructions, not {:?}",
+                    self.metric
+                ));
+            }
+            if self.small_multiples {
+                return Err(eyre!(
+                    "--plot-kind stacked cannot be combined with --small-multiples"
+                ));
+            }
+            if self.baseline.is_some() {
+                return Err(eyre!(
+                    "--baseline is only drawn on line plots, not with --plot-kind stacked"
+                ));
+            }
+        }
+        if self.x_axis == XAxis::Execs {
+            if self.metric == Metric::Bugs {
+                return Err(eyre!(
+                    "Bugs are only plotted over time, not with --x-axis execs"
+                ));
+            }
+            if self.plot_kind == PlotKind::Stacked || self.small_multiples {
+                return Err(eyre!("--x-axis execs only applies to the line plot"));
+            }
+            if self.show_timeout {
+                return Err(eyre!(
+                    "--show-timeout marks a time, it cannot be combined with --x-axis execs"
+                ));
+            }
+        }
+        if let Some(db_path) = &self.sqlite {
+            if !db_path.is_file() {
+                return Err(eyre!(
+                    "SQLite database {} does not exist",
+                    db_path.display()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Bucket size used to deduplicate parsed coverage samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TimeResolution {
+    /// Keep every sample, even several per millisecond
+    Raw,
+    /// One sample per millisecond
+    Ms,
+    /// One sample per second, to shrink the CSVs of long runs
+    S,
+}
+
+impl TimeResolution {
+    /// Bucket a sample taken `time_millis` after the start falls into
+    pub fn bucket(self, time_millis: u64) -> Option<u64> {
+        match self {
+            TimeResolution::Raw => None,
+            TimeResolution::Ms => Some(time_millis),
+            TimeResolution::S => Some(time_millis / 1000),
+        }
+    }
+}
+
+/// Unit of the timestamps printed by the fuzzer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampField {
+    /// `time-nanos`, nanoseconds since the epoch
+    Nanos,
+    /// `time-millis`, milliseconds since the epoch
+    #[default]
+    Millis,
+    /// `time-seconds`, seconds since the epoch
+    Seconds,
+}
+
+impl TimestampField {
+    /// Converts a log timestamp to milliseconds, the unit of the stats CSVs
+    pub fn to_millis(self, timestamp: u64) -> u64 {
+        match self {
+            TimestampField::Nanos => timestamp / 1_000_000,
+            TimestampField::Millis => timestamp,
+            TimestampField::Seconds => timestamp.saturating_mul(1000),
+        }
+    }
+}
+
+/// Origin of each contract's time axis in the aggregate plot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Align {
+    /// Time relative to the fuzzer's `start at` line
+    Zero,
+    /// Shift each contract so its first coverage sample is at t=0
+    FirstSample,
+}
+
+/// How per-contract values are combined into the aggregate series
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AggregateMode {
+    /// Total over all contracts
+    Sum,
+    /// Largest value of any contract
+    Max,
+    /// Average over contracts
+    Mean,
+}
+
+/// Statistic used to combine repeated runs of the same contract
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Aggregate {
+    Mean,
+    /// Robust to outlier runs
+    Median,
+    /// Highest coverage at each timestamp
+    Best,
+    /// Lowest coverage at each timestamp
+    Worst,
+}
+
+/// Shape of the aggregate plot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PlotKind {
+    /// One line of the combined coverage, plus one per group
+    Line,
+    /// Each contract's covered instructions stacked on top of each other
+    Stacked,
+}
+
+/// Quantity plotted on the x axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum XAxis {
+    /// Seconds since the fuzzer started
+    Time,
+    /// Executions the fuzzer reported with each coverage sample, in thousands
+    Execs,
+}
+
+impl XAxis {
+    /// Description of the x axis in charts
+    pub fn label(self) -> &'static str {
+        match self {
+            XAxis::Time => "Time (seconds)",
+            XAxis::Execs => "Executions (thousands)",
+        }
+    }
+
+    /// Name of the x column in the overall CSVs
+    pub fn csv_column(self) -> &'static str {
+        match self {
+            XAxis::Time => "time_seconds",
+            XAxis::Execs => "execs(k)",
+        }
+    }
+}
+
+/// Scale of the instruction counts shown on the y axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum YUnit {
+    Auto,
+    /// Raw instruction counts
+    Raw,
+    /// Instructions / 10^3
+    Thousands,
+    /// Instructions / 10^6
+    Millions,
+}
+
+#[derive(Parser, Debug)]
+pub struct CleanArgs {
+    /// Analysis output directory to clean
+    #[arg(short, long, value_name = "DIR", default_value = "analysis_output")]
+    pub output_dir: PathBuf,
+
+    /// Also remove the fuzzer work directories under `--workdir-root`
+    #[arg(long)]
+    pub include_workdirs: bool,
+
+    /// Root of the fuzzer work directories, as passed to `run`
+    #[arg(long, value_name = "DIR", default_value = WORK_DIRS_ROOT)]
+    pub workdir_root: PathBuf,
+
+    /// Only list the files that would be removed
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct DiffArgs {
+    /// Output directory of the reference run
+    #[arg(long, value_name = "DIR")]
+    pub baseline: PathBuf,
+
+    /// Output directory of the run to check
+    #[arg(long, value_name = "DIR")]
+    pub current: PathBuf,
+
+    /// Fail if any contract's final coverage dropped by more than this percentage
+    #[arg(long, value_name = "PERCENT", default_value_t = 0.0)]
+    pub threshold: f64,
+}
+
+/// Metric drawn on the y axis of the aggregate plot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Metric {
+    /// Cumulative number of covered instructions
+    Instructions,
+    /// Newly covered instructions per second
+    Rate,
+    /// Covered instructions as a percentage of all instructions, capped at 100
+    CoveragePercent,
+    /// Cumulative number of bugs found, read from the `*.bugs.csv` files
+    Bugs,
+}
+
+/// How progress is reported on the console
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProgressMode {
+    /// Animated bar on a terminal, plain lines otherwise
+    Auto,
+    /// Always draw the animated progress bar
+    Bar,
+    /// Print plain text progress lines, suitable for CI logs
+    Plain,
+    /// Do not report progress
+    None,
+}
+
+/// Number of distinct bugs a contract run had found at a point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BugEntry {
+    pub time_taken_millis: u64,
+    pub bugs_found: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StatsEntry {
     pub instructions_covered: u64,
     pub branches_covered: u64,
-    // Exists in log but not used
+    // Exists in log but not used; missing from CSVs written by older versions
+    #[serde(default)]
     pub total_instructions: u64,
-    // pub total_coverages: u64,
     pub time_taken_millis: u64,
+    /// Missing from CSVs written by older versions
+    #[serde(default)]
+    pub total_branches: u64,
+    /// Coverage reported directly as a percentage by fuzzers that print no
+    /// instruction counts; the counts are 0 then
+    #[serde(default)]
+    pub coverage_percent: Option<f64>,
+    /// Unix epoch time of the sample, the start timestamp plus
+    /// `time_taken_millis`; unknown for combined repetitions
+    #[serde(default)]
+    pub abs_timestamp_millis: Option<u64>,
+    /// Executions the fuzzer reported with the sample, if it logs them
+    #[serde(default)]
+    pub execs: Option<u64>,
+}
+
+impl StatsEntry {
+    /// Instruction coverage in percent, from the counts when known and the
+    /// reported percentage otherwise
+    pub fn instruction_percent(&self) -> Option<f64> {
+        if self.total_instructions > 0 {
+            Some(self.instructions_covered as f64 / self.total_instructions as f64 * 100.0)
+        } else {
+            self.coverage_percent
+        }
+    }
+}
+
+/// How the fuzzer process finished, derived from its exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitKind {
+    /// Exited with code 0
+    Success,
+    /// Killed by `timeout` (exit code 124) while still printing coverage
+    Timeout,
+    /// Killed by `timeout` after printing no coverage line for `--hang-seconds`
+    Hung,
+    /// Exited with one of the `--success-exit-codes`
+    AcceptedExitCode,
+    /// Any other exit code, or killed by a signal
+    Failure,
+    /// Terminated early because coverage stopped increasing (`--plateau-seconds`)
+    PlateauStopped,
+}
+
+/// Per-contract run information written next to the stats CSV
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContractMeta {
+    pub contract_id: String,
+    pub exit_code: Option<i32>,
+    pub exit_kind: ExitKind,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Variables passed with `--env`
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Program and arguments the fuzzer was started with
+    #[serde(default)]
+    pub invocation: Vec<String>,
+    /// Timeout the fuzzer ran under, see `--timeout-scale`
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+}
+
+/// Settings of a `run` invocation, written to `run_manifest.json`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub started_at: String,
+    pub fuzzer_path: String,
+    /// Output of `--fuzzer-version-flag`, or the binary's mtime and hash
+    #[serde(default)]
+    pub fuzzer_version: Option<String>,
+    pub fuzzer_options: Vec<String>,
+    pub fuzz_timeout_seconds: u64,
+    pub seed: Option<u64>,
+    pub repeat: u32,
+    /// Options swept with `--matrix` and `--option-matrix`
+    #[serde(default)]
+    pub option_matrix: Vec<MatrixAxis>,
+    /// Unit the log timestamps were read in
+    #[serde(default)]
+    pub timestamp_field: TimestampField,
+    /// Seed that chose the contracts of a `--sample` or `--sample-n` run;
+    /// pass it as `--seed` to fuzz the same contracts again
+    #[serde(default)]
+    pub sample_seed: Option<u64>,
+    /// Ids of the runs, one per contract and repetition
+    pub runs: Vec<String>,
+}
+
+/// Problem found while running or parsing one contract, written to `warnings.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AnalysisWarning {
+    /// The log has no `Ityfuzz start at` line
+    MissingStartTimestamp { contract_id: String },
+    /// A coverage sample is older than the one before it
+    NonMonotonicCoverage {
+        contract_id: String,
+        timestamp_millis: u64,
+        previous_millis: u64,
+    },
+    /// More than an hour passed between two coverage samples
+    SuspiciousGap {
+        contract_id: String,
+        timestamp_millis: u64,
+        previous_millis: u64,
+    },
+    /// A coverage sample is older than the start timestamp and was dropped
+    SampleBeforeStart {
+        contract_id: String,
+        timestamp_millis: u64,
+        started_at_millis: u64,
+    },
+    /// Some target files are empty, so the fuzzer was not started
+    EmptyTarget {
+        contract_id: String,
+        files: Vec<String>,
+    },
+    /// The fuzzer printed nothing, or no coverage stat lines
+    EmptyLog { contract_id: String },
+    /// Output lines with invalid UTF-8 bytes, which were replaced before
+    /// parsing and may have mangled a sample
+    InvalidUtf8 { contract_id: String, lines: usize },
+    /// The log, or the fuzzer run itself, failed
+    ParseFailure {
+        contract_id: String,
+        message: String,
+    },
+}
+
+/// How one run ended, counted in the summary printed after the run command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RunOutcome {
+    /// The fuzzer could not be started
+    SpawnError,
+    /// Killed by the timeout before printing anything
+    TimeoutNoOutput,
+    /// The log could not be parsed
+    ParseError,
+    /// The fuzzer printed nothing, or no coverage stat lines
+    EmptyLog,
+    /// The results could not be written
+    WriteError,
+    /// The fuzzer was not started, e.g. for empty targets or after an abort
+    Skipped,
+    Succeeded,
+}
+
+impl RunOutcome {
+    /// Name of the category in the summary
+    pub fn label(self) -> &'static str {
+        match self {
+            RunOutcome::SpawnError => "Spawn errors",
+            RunOutcome::TimeoutNoOutput => "Timeouts without output",
+            RunOutcome::ParseError => "Parse errors",
+            RunOutcome::EmptyLog => "Empty logs",
+            RunOutcome::WriteError => "Write errors",
+            RunOutcome::Skipped => "Skipped",
+            RunOutcome::Succeeded => "Succeeded",
+        }
+    }
 }
@@ -1,14 +1,34 @@
 use std::{
-    fs::{self, File}, io::{BufRead, BufReader}, path::PathBuf, process::{Command, Stdio}
+    collections::{BTreeMap, HashSet},
+    env,
+    fs::{self, File},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
 };
 
-use crate::types::{CompileArgs, PTXArgs};
-use eyre::{Context, Result, eyre};
-use indicatif::{ProgressBar, ProgressStyle};
-use tracing::{error, info};
+use crate::progress::Progress;
+use crate::run::normalize_line;
+use crate::types::{CompileArgs, ListEntry, ListFormat, PTXArgs, PtxToolchainArgs};
 use dirs::home_dir;
+use eyre::{Context, Result, eyre};
 use glob::glob;
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+
+/// Intermediate LLVM files written by `generate_ptx` next to `kernel.ptx`
+const PTX_INTERMEDIATE_FILES: [&str; 3] = ["bytecode.ll", "kernel.bc", "kernel.ll"];
+
+/// Captured solc output of a failed compile, written to the contract's output directory
+const SOLC_ERROR_LOG_FILE: &str = "solc_error.log";
+
+/// List of the contracts that compiled successfully, written to the solc output directory
+const COMPILED_LIST_FILE: &str = "compiled.list";
 
+/// SHA-256 of every artifact kept for a compiled contract, in `sha256sum`
+/// format, written to the solc output directory
+pub const CHECKSUMS_FILE: &str = "checksums.txt";
 
 pub fn handle_compile_command(args: CompileArgs) -> Result<()> {
     info!("Starting contract compilation and filtering process...");
@@ -33,6 +53,13 @@ pub fn handle_compile_command(args: CompileArgs) -> Result<()> {
         ));
     }
 
+    let rt_bitcode = if args.generate_ptx {
+        check_ptx_toolchain(&args.ptx_toolchain)?;
+        Some(resolve_rt_bitcode(&args.ptx_toolchain.rt_bitcode)?)
+    } else {
+        None
+    };
+
     fs::create_dir_all(&args.solc_output_dir).wrap_err_with(|| {
         format!(
             "Failed to create base output directory: {}",
@@ -40,54 +67,25 @@ pub fn handle_compile_command(args: CompileArgs) -> Result<()> {
         )
     })?;
 
-    let file = File::open(&args.list_file)
-        .wrap_err_with(|| format!("Failed to open list file: {}", args.list_file.display()))?;
-    let reader = BufReader::new(file);
-
-    // Count total lines first for progress bar
-    let total_lines = reader.lines().count();
-    let file = File::open(&args.list_file)?;
-    let reader = BufReader::new(file);
+    let list_entries = match args.list_format {
+        ListFormat::Csv => read_csv_list(&args.list_file)?,
+        ListFormat::Json => read_json_list(&args.list_file)?,
+    };
 
-    let pb = ProgressBar::new(total_lines as u64);
-    pb.set_style(
-        ProgressStyle::with_template(
-            "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})\n{msg}",
-        )
-            .unwrap()
-            .progress_chars("█▓▒░ "),
-    );
+    let pb = Progress::new(list_entries.len() as u64, args.progress);
     pb.set_message("Starting compilation...");
 
-    for (line_number, line_result) in reader.lines().enumerate() {
-        pb.inc(1);
-        let line = line_result.wrap_err_with(|| {
-            format!(
-                "Failed to read line {} from {}",
-                line_number + 1,
-                args.list_file.display()
-            )
-        })?;
-        let line_trimmed = line.trim();
-
-        if line_trimmed.is_empty() || line_trimmed.starts_with('#') {
-            continue;
-        }
-
-        let parts: Vec<&str> = line_trimmed.split(',').map(|s| s.trim()).collect();
-        if parts.len() < 2 || parts[0].is_empty() || parts[1].is_empty() {
-            info!(
-                "Warning: Skipping malformed line {} in {}: '{}'",
-                line_number + 1,
-                args.list_file.display(),
-                line
-            );
-            continue;
-        }
+    let optimize_runs = args.optimize_runs.map(|runs| runs.to_string());
+    // Compiler versions already confirmed to be installed by solc-select
+    let mut installed_solc_versions: HashSet<String> = HashSet::new();
+    let mut compiled_entries: Vec<&ListEntry> = Vec::new();
+    let mut kept_artifacts: Vec<PathBuf> = Vec::new();
 
-        let sol_filename_base = parts[0];
-        let main_contract_name = parts[1];
-        let compiler_version = parts.get(2).map(|s| s.trim().to_owned());
+    for entry in &list_entries {
+        pb.inc(1);
+        let sol_filename_base = entry.file.as_str();
+        let main_contract_name = entry.contract.as_str();
+        let compiler_version = entry.version.clone();
 
         let sol_file_path = args
             .solc_input_dir
@@ -96,7 +94,7 @@ pub fn handle_compile_command(args: CompileArgs) -> Result<()> {
             info!(
                 "Warning: Solidity file {} not found for entry '{}'. Skipping.",
                 sol_file_path.display(),
-                line
+                sol_filename_base
             );
             continue;
         }
@@ -119,7 +117,7 @@ pub fn handle_compile_command(args: CompileArgs) -> Result<()> {
         let sol_file_path_str = sol_file_path.to_string_lossy();
         let specific_output_dir_str = specific_output_dir.to_string_lossy();
         // Run solc
-        let solc_args = [
+        let mut solc_args = vec![
             "--bin",
             "--bin-runtime",
             "--abi",
@@ -130,14 +128,41 @@ pub fn handle_compile_command(args: CompileArgs) -> Result<()> {
             "-o",
             specific_output_dir_str.as_ref(),
         ];
+        if entry.optimize.unwrap_or(args.optimize) {
+            solc_args.push("--optimize");
+        }
+        if let Some(ref optimize_runs) = optimize_runs {
+            solc_args.push("--optimize-runs");
+            solc_args.push(optimize_runs);
+        }
+        if let Some(ref evm_version) = args.evm_version {
+            solc_args.push("--evm-version");
+            solc_args.push(evm_version);
+        }
 
-
-        let solc_binary: String = match (&args.solc_binary, compiler_version){
+        let solc_binary: String = match (&args.solc_binary, compiler_version) {
             (Some(solc_binary), _) => solc_binary.to_string_lossy().into_owned(),
             (None, Some(ref version)) => {
-                format!("{}/.solc-select/artifacts/solc-{}/solc-{}", home_dir().unwrap().as_os_str().to_string_lossy(), version, version)
-            },
-            _ => "solc".into()
+                let solc_path = solc_select_binary_path(version);
+                if !installed_solc_versions.contains(version) {
+                    if solc_path.exists() {
+                        installed_solc_versions.insert(version.clone());
+                    } else if args.auto_install_solc {
+                        install_solc_version(version)?;
+                        installed_solc_versions.insert(version.clone());
+                    } else {
+                        error!(
+                            "  ERROR: solc {} not found at {} (use --auto-install-solc to install it with solc-select)",
+                            version,
+                            solc_path.display()
+                        );
+                        failed_contracts.push(sol_filename_base.to_string());
+                        continue;
+                    }
+                }
+                solc_path.to_string_lossy().into_owned()
+            }
+            _ => "solc".into(),
         };
 
         info!("  Compiling with: solc {}", solc_args.join(" "));
@@ -146,19 +171,14 @@ pub fn handle_compile_command(args: CompileArgs) -> Result<()> {
         command
             .arg(format!("{}s", args.solc_timeout_seconds))
             .arg(&solc_binary)
-            .args(solc_args)
+            .args(&solc_args)
             .stdout(Stdio::null()) // Use piped might block the thread if we don't process the output
             .stderr(Stdio::null());
 
         info!("  Running with timeout: {:?}", command);
         let solc_status = command
             .status() // Use status() for simple success/failure, or output() to capture
-            .wrap_err_with(|| {
-                format!(
-                    "Failed to execute solc ({}) with timeout. ",
-                    solc_binary
-                )
-            })?;
+            .wrap_err_with(|| format!("Failed to execute solc ({}) with timeout. ", solc_binary))?;
 
         let mut compilation_success = solc_status.success();
 
@@ -173,7 +193,10 @@ pub fn handle_compile_command(args: CompileArgs) -> Result<()> {
                 abi_path.exists() && bin_path.exists() && bin_runtime_path.exists();
 
             if !compilation_success {
-                info!("  ERROR: Output files missing for {}", sol_filename_base);
+                error!("  ERROR: Output files missing for {}", sol_filename_base);
+            } else if let Err(e) = validate_compiled_output(&abi_path, &bin_path) {
+                error!("  ERROR: Invalid output for {}: {}", sol_filename_base, e);
+                compilation_success = false;
             }
         }
 
@@ -182,17 +205,52 @@ pub fn handle_compile_command(args: CompileArgs) -> Result<()> {
                 "  ERROR: Solc compilation failed for {} with status: {}",
                 sol_filename_base, solc_status
             );
-            failed_contracts.push(sol_filename_base.to_string());
+            // Successful compiles stay on the null-piped path above; only
+            // failures are re-run to capture solc's output.
+            let solc_log_path = specific_output_dir.join(SOLC_ERROR_LOG_FILE);
+            match write_solc_error_log(&command, &solc_log_path) {
+                Ok(()) => {
+                    info!("  solc output saved to {}", solc_log_path.display());
+                    failed_contracts.push(format!(
+                        "{} (solc log: {})",
+                        sol_filename_base,
+                        solc_log_path.display()
+                    ));
+                }
+                Err(e) => {
+                    error!(
+                        "  ERROR: Failed to capture solc output for {}: {:?}",
+                        sol_filename_base, e
+                    );
+                    failed_contracts.push(sol_filename_base.to_string());
+                }
+            }
             continue;
         }
         info!("  Compilation successful for {}.", sol_filename_base);
 
         // Generate PTX files if enabled
-        if args.generate_ptx {
-            if let Err(e) = generate_ptx(sol_filename_base, main_contract_name) {
-                error!("  ERROR: Failed to generate PTX for {}: {}", sol_filename_base, e);
+        let compiled = if let Some(rt_bitcode) = &rt_bitcode {
+            if let Err(e) = generate_ptx(
+                &specific_output_dir,
+                main_contract_name,
+                &args.ptx_toolchain,
+                rt_bitcode,
+            ) {
+                error!(
+                    "  ERROR: Failed to generate PTX for {}: {}",
+                    sol_filename_base, e
+                );
                 failed_contracts.push(sol_filename_base.to_string());
+                false
+            } else {
+                true
             }
+        } else {
+            true
+        };
+        if compiled {
+            compiled_entries.push(entry);
         }
 
         let entries = fs::read_dir(&specific_output_dir).wrap_err_with(|| {
@@ -212,10 +270,19 @@ pub fn handle_compile_command(args: CompileArgs) -> Result<()> {
                 let filename_str = filename_osstr.to_string_lossy();
                 let file_prefix_to_keep = format!("{}.", main_contract_name);
 
-                if filename_str.starts_with(&file_prefix_to_keep) || filename_str.ends_with(".ptx")
+                let is_kept_ptx_intermediate = args.keep_ptx_intermediates
+                    && PTX_INTERMEDIATE_FILES.contains(&filename_str.as_ref());
+
+                if filename_str.starts_with(&file_prefix_to_keep)
+                    || filename_str.ends_with(".ptx")
+                    || is_kept_ptx_intermediate
+                    || is_kept_by_keep_ext(&file_path, &args.keep_ext)
                 {
                     info!("    Keeping: {}", filename_str);
                     kept_count += 1;
+                    if compiled {
+                        kept_artifacts.push(file_path);
+                    }
                 } else {
                     info!("    Removing: {}", filename_str);
                     fs::remove_file(&file_path).wrap_err_with(|| {
@@ -235,6 +302,21 @@ pub fn handle_compile_command(args: CompileArgs) -> Result<()> {
 
     info!("\nAll contract processing finished.");
 
+    let compiled_list_path = args.solc_output_dir.join(COMPILED_LIST_FILE);
+    write_csv_list(&compiled_list_path, &compiled_entries)?;
+    info!(
+        "Wrote {} compiled contracts to {}",
+        compiled_entries.len(),
+        compiled_list_path.display()
+    );
+    let checksums_path = args.solc_output_dir.join(CHECKSUMS_FILE);
+    write_checksums(&checksums_path, &args.solc_output_dir, &kept_artifacts)?;
+    info!(
+        "Wrote checksums of {} artifacts to {}",
+        kept_artifacts.len(),
+        checksums_path.display()
+    );
+
     if !failed_contracts.is_empty() {
         info!("\nFailed to compile {} contracts:", failed_contracts.len());
         for contract in failed_contracts {
@@ -247,75 +329,404 @@ pub fn handle_compile_command(args: CompileArgs) -> Result<()> {
     Ok(())
 }
 
+/// Whether `--keep-ext` names the file itself or its extension
+fn is_kept_by_keep_ext(file_path: &Path, keep_ext: &[String]) -> bool {
+    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
+    let extension = file_path.extension().unwrap_or_default().to_string_lossy();
+    keep_ext.iter().any(|keep| {
+        *keep == file_name || (!extension.is_empty() && keep.trim_start_matches('.') == extension)
+    })
+}
+
+/// Reads a `<file>,<contract>[,<version>]` list, skipping blank, comment and malformed lines
+pub fn read_csv_list(list_file: &Path) -> Result<Vec<ListEntry>> {
+    let file = File::open(list_file)
+        .wrap_err_with(|| format!("Failed to open list file: {}", list_file.display()))?;
+    let reader = BufReader::new(file);
+
+    let mut entries = Vec::new();
+    for (line_number, line_result) in reader.lines().enumerate() {
+        let line = line_result.wrap_err_with(|| {
+            format!(
+                "Failed to read line {} from {}",
+                line_number + 1,
+                list_file.display()
+            )
+        })?;
+        let line_trimmed = normalize_line(&line).trim();
+
+        if line_trimmed.is_empty() || line_trimmed.starts_with('#') {
+            continue;
+        }
+
+        let parts: Vec<&str> = line_trimmed.split(',').map(|s| s.trim()).collect();
+        if parts.len() < 2 || parts[0].is_empty() || parts[1].is_empty() {
+            info!(
+                "Warning: Skipping malformed line {} in {}: '{}'",
+                line_number + 1,
+                list_file.display(),
+                line
+            );
+            continue;
+        }
+
+        entries.push(ListEntry {
+            file: parts[0].to_owned(),
+            contract: parts[1].to_owned(),
+            version: parts.get(2).map(|s| s.to_string()),
+            optimize: None,
+        });
+    }
+    Ok(entries)
+}
+
+/// Writes entries in the `file,contract[,version]` list format read by `read_csv_list`,
+/// merged by file into the entries already in `list_file`
+fn write_csv_list(list_file: &Path, entries: &[&ListEntry]) -> Result<()> {
+    let mut merged = if list_file.exists() {
+        read_csv_list(list_file)?
+    } else {
+        Vec::new()
+    };
+    for entry in entries {
+        match merged
+            .iter_mut()
+            .find(|existing| existing.file == entry.file)
+        {
+            Some(existing) => *existing = (*entry).clone(),
+            None => merged.push((*entry).clone()),
+        }
+    }
+
+    let mut file = File::create(list_file)
+        .wrap_err_with(|| format!("Failed to create list file: {}", list_file.display()))?;
+    for entry in &merged {
+        let line = match &entry.version {
+            Some(version) => format!("{},{},{}", entry.file, entry.contract, version),
+            None => format!("{},{}", entry.file, entry.contract),
+        };
+        writeln!(file, "{}", line)
+            .wrap_err_with(|| format!("Failed to write list file: {}", list_file.display()))?;
+    }
+    Ok(())
+}
+
+/// Hex-encoded SHA-256 of a file's content
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file =
+        File::open(path).wrap_err_with(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Writes the checksums of `artifacts`, named relative to `base_dir`, merged by
+/// name into the checksums already in `checksums_path`
+fn write_checksums(checksums_path: &Path, base_dir: &Path, artifacts: &[PathBuf]) -> Result<()> {
+    let mut checksums = if checksums_path.exists() {
+        read_checksums(checksums_path)?
+    } else {
+        BTreeMap::new()
+    };
+    for artifact in artifacts {
+        let name = artifact.strip_prefix(base_dir).unwrap_or(artifact);
+        checksums.insert(name.to_path_buf(), sha256_file(artifact)?);
+    }
+
+    let mut file = File::create(checksums_path).wrap_err_with(|| {
+        format!(
+            "Failed to create checksums file: {}",
+            checksums_path.display()
+        )
+    })?;
+    for (name, checksum) in &checksums {
+        writeln!(file, "{}  {}", checksum, name.display()).wrap_err_with(|| {
+            format!(
+                "Failed to write checksums file: {}",
+                checksums_path.display()
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Reads a checksums file written by `write_checksums`, keyed by artifact path
+/// relative to the solc output directory
+pub fn read_checksums(checksums_path: &Path) -> Result<BTreeMap<PathBuf, String>> {
+    let content = fs::read_to_string(checksums_path).wrap_err_with(|| {
+        format!(
+            "Failed to read checksums file: {}",
+            checksums_path.display()
+        )
+    })?;
+    let mut checksums = BTreeMap::new();
+    for (line_number, line) in content.lines().map(normalize_line).enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (checksum, name) = line.split_once("  ").ok_or_else(|| {
+            eyre!(
+                "Malformed line {} in {}: '{}'",
+                line_number + 1,
+                checksums_path.display(),
+                line
+            )
+        })?;
+        checksums.insert(PathBuf::from(name), checksum.to_string());
+    }
+    Ok(checksums)
+}
+
+/// Reads a JSON array of list entries
+fn read_json_list(list_file: &Path) -> Result<Vec<ListEntry>> {
+    let file = File::open(list_file)
+        .wrap_err_with(|| format!("Failed to open list file: {}", list_file.display()))?;
+    serde_json::from_reader(BufReader::new(file))
+        .wrap_err_with(|| format!("Failed to parse JSON list file: {}", list_file.display()))
+}
+
+/// Resolves a tool name via PATH, or checks it directly when it contains a path separator
+fn resolve_tool(tool: &str) -> Option<PathBuf> {
+    let tool_path = Path::new(tool);
+    if tool_path.components().count() > 1 {
+        return tool_path.is_file().then(|| tool_path.to_path_buf());
+    }
+    env::var_os("PATH").and_then(|paths| {
+        env::split_paths(&paths)
+            .map(|dir| dir.join(tool))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Fails early with the list of PTX pipeline tools that cannot be found
+fn check_ptx_toolchain(toolchain: &PtxToolchainArgs) -> Result<()> {
+    let missing_tools: Vec<&str> = [
+        &toolchain.ptxsema,
+        &toolchain.llvm_link,
+        &toolchain.llvm_dis,
+        &toolchain.llc,
+    ]
+    .into_iter()
+    .filter(|tool| resolve_tool(tool).is_none())
+    .map(String::as_str)
+    .collect();
+
+    if !missing_tools.is_empty() {
+        return Err(eyre!(
+            "PTX generation requested but these tools were not found in PATH: {}",
+            missing_tools.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Re-runs a failed solc `command` with its output captured and writes the
+/// exact command line, exit status, stdout and stderr to `log_path`
+fn write_solc_error_log(command: &Command, log_path: &Path) -> Result<()> {
+    let program = command.get_program().to_string_lossy();
+    let args: Vec<_> = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy())
+        .collect();
+    let output = Command::new(command.get_program())
+        .args(command.get_args())
+        .stdin(Stdio::null())
+        .output()
+        .wrap_err_with(|| format!("Failed to re-run {}", program))?;
+
+    let log = format!(
+        "$ {} {}\nstatus: {}\n\n--- stdout ---\n{}\n--- stderr ---\n{}",
+        program,
+        args.join(" "),
+        output.status,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    fs::write(log_path, log)
+        .wrap_err_with(|| format!("Failed to write solc log {}", log_path.display()))?;
+    Ok(())
+}
+
+/// Path of a solc binary installed by solc-select
+fn solc_select_binary_path(version: &str) -> PathBuf {
+    home_dir()
+        .unwrap()
+        .join(".solc-select/artifacts")
+        .join(format!("solc-{}", version))
+        .join(format!("solc-{}", version))
+}
+
+/// Installs a solc version with `solc-select install <version>`
+fn install_solc_version(version: &str) -> Result<()> {
+    info!("  Installing solc {} with solc-select", version);
+    let status = Command::new("solc-select")
+        .arg("install")
+        .arg(version)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .wrap_err("Failed to run solc-select, is it installed and in PATH?")?;
+
+    if !status.success() {
+        return Err(eyre!(
+            "solc-select install {} failed with status: {}",
+            version,
+            status
+        ));
+    }
+
+    let solc_path = solc_select_binary_path(version);
+    if !solc_path.exists() {
+        return Err(eyre!(
+            "solc-select install {} succeeded but {} does not exist",
+            version,
+            solc_path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that the deployment bytecode is non-empty hex and that the ABI is
+/// valid JSON. solc emits an empty `.bin` for abstract contracts and
+/// interfaces, which cannot be fuzzed.
+fn validate_compiled_output(abi_path: &Path, bin_path: &Path) -> Result<()> {
+    let bytecode = fs::read_to_string(bin_path)
+        .wrap_err_with(|| format!("Failed to read {}", bin_path.display()))?;
+    let bytecode = bytecode.trim();
+    if bytecode.is_empty() {
+        return Err(eyre!("{} is empty", bin_path.display()));
+    }
+    if !bytecode.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(eyre!("{} does not contain valid hex", bin_path.display()));
+    }
+
+    let abi = fs::read_to_string(abi_path)
+        .wrap_err_with(|| format!("Failed to read {}", abi_path.display()))?;
+    serde_json::from_str::<serde_json::Value>(&abi)
+        .wrap_err_with(|| format!("Failed to parse ABI {}", abi_path.display()))?;
+
+    Ok(())
+}
+
+/// Absolute path of the runtime bitcode linked into every kernel, resolved
+/// against the current directory so it stays valid in each contract folder
+fn resolve_rt_bitcode(rt_bitcode: &Path) -> Result<PathBuf> {
+    fs::canonicalize(rt_bitcode).wrap_err_with(|| {
+        format!(
+            "Runtime bitcode {} not found; pass its location with --rt-bitcode",
+            rt_bitcode.display()
+        )
+    })
+}
+
 /// Generates PTX files for a given contract binary folder and main contract
-/// name. Assuming contract deployment binary has already been generated
-fn generate_ptx(contract_binary_folder_path: &str, main_contract_name: &str)->Result<()>{
-    info!("  Generating PTX files for {} ", contract_binary_folder_path);
-    let contract_binary_folder = PathBuf::from(contract_binary_folder_path);
+/// name, linking the absolute `rt_bitcode`. Assuming contract deployment
+/// binary has already been generated
+fn generate_ptx(
+    contract_binary_folder: &Path,
+    main_contract_name: &str,
+    toolchain: &PtxToolchainArgs,
+    rt_bitcode: &Path,
+) -> Result<()> {
+    info!(
+        "  Generating PTX files for {} ",
+        contract_binary_folder.display()
+    );
 
     let bin_path = contract_binary_folder.join(format!("{}.bin", main_contract_name));
-    let bytecode_ll = contract_binary_folder.join("bytecode.ll");
-    let kernel_bc = contract_binary_folder.join("kernel.bc");
-    let kernel_ll = contract_binary_folder.join("kernel.ll");
+    let [bytecode_ll, kernel_bc, kernel_ll] =
+        PTX_INTERMEDIATE_FILES.map(|file_name| contract_binary_folder.join(file_name));
     let kernel_ptx = contract_binary_folder.join("kernel.ptx");
 
     // Step 1: Generate bytecode.ll
-    let status = Command::new("ptxsema")
+    let status = Command::new(&toolchain.ptxsema)
         .arg(bin_path)
         .arg("-o")
         .arg(&bytecode_ll)
         .arg("--hex")
         .arg("--dump")
         .status()
-        .wrap_err("Failed to run ptxsema")?;
+        .wrap_err_with(|| format!("Failed to run {}", toolchain.ptxsema))?;
 
     if !status.success() {
-        return Err(eyre!("ptxsema failed for {}", contract_binary_folder.display()));
+        return Err(eyre!(
+            "{} failed for {}",
+            toolchain.ptxsema,
+            contract_binary_folder.display()
+        ));
     }
 
-    let status = Command::new("llvm-link")
-        .arg("rt.o.bc")
-        .arg(&bytecode_ll)
+    // Step 2: Link with the runtime bitcode. llvm-link runs in the binary
+    // folder, so a relative tool path has to be resolved from here first
+    let llvm_link = resolve_tool(&toolchain.llvm_link)
+        .and_then(|tool| fs::canonicalize(tool).ok())
+        .unwrap_or_else(|| PathBuf::from(&toolchain.llvm_link));
+    let status = Command::new(&llvm_link)
+        .current_dir(contract_binary_folder)
+        .arg(rt_bitcode)
+        .arg(bytecode_ll.file_name().unwrap_or_default())
         .arg("-o")
-        .arg(&kernel_bc)
+        .arg(kernel_bc.file_name().unwrap_or_default())
         .status()
-        .wrap_err("Failed to run llvm-link")?;
+        .wrap_err_with(|| format!("Failed to run {}", toolchain.llvm_link))?;
 
     if !status.success() {
-        return Err(eyre!("llvm-link failed for {}", contract_binary_folder_path));
+        return Err(eyre!(
+            "{} failed for {}",
+            toolchain.llvm_link,
+            contract_binary_folder.display()
+        ));
     }
 
     // Step 3: Disassemble to human-readable LLVM IR
-    let status = Command::new("llvm-dis")
+    let status = Command::new(&toolchain.llvm_dis)
         .arg(&kernel_bc)
         .arg("-o")
         .arg(&kernel_ll)
         .status()
-        .wrap_err("Failed to run llvm-dis")?;
+        .wrap_err_with(|| format!("Failed to run {}", toolchain.llvm_dis))?;
 
     if !status.success() {
-        return Err(eyre!("llvm-dis failed for {}", contract_binary_folder_path));
+        return Err(eyre!(
+            "{} failed for {}",
+            toolchain.llvm_dis,
+            contract_binary_folder.display()
+        ));
     }
 
     // Step 4: Generate PTX
-    let status = Command::new("llc-16")
-        .arg("-mcpu=sm_86")
+    let status = Command::new(&toolchain.llc)
+        .arg(format!("-mcpu={}", toolchain.mcpu))
         .arg(&kernel_bc)
         .arg("-o")
         .arg(&kernel_ptx)
         .status()
-        .wrap_err("Failed to run llc-16")?;
+        .wrap_err_with(|| format!("Failed to run {}", toolchain.llc))?;
 
     if !status.success() {
-        return Err(eyre!("llc-16 failed for {}", contract_binary_folder_path));
+        return Err(eyre!(
+            "{} failed for {}",
+            toolchain.llc,
+            contract_binary_folder.display()
+        ));
     }
 
-    info!("  PTX generation complete for {}", contract_binary_folder_path);
+    info!(
+        "  PTX generation complete for {}",
+        contract_binary_folder.display()
+    );
     Ok(())
 }
 
-
 pub fn handle_ptx_command(args: PTXArgs) -> Result<()> {
+    check_ptx_toolchain(&args.ptx_toolchain)?;
+    let rt_bitcode = resolve_rt_bitcode(&args.ptx_toolchain.rt_bitcode)?;
+
     let pattern = format!("{}/*/*.bin", args.solc_output_dir.display());
     info!("Searching for *.bin files matching pattern: {}", pattern);
 
@@ -324,17 +735,109 @@ pub fn handle_ptx_command(args: PTXArgs) -> Result<()> {
         .filter_map(Result::ok)
         .collect::<Vec<PathBuf>>();
 
-    found_binaries.iter().for_each(|bin_path| {
-        info!("Found binary file: {}", bin_path.display());
-        let contract_binary_folder = bin_path.parent()
-            .expect("Binary file should have a parent directory");
-        let main_contract_name = bin_path.file_stem()
-            .and_then(|s| s.to_str())
-            .expect("Binary file should have a valid name");
-        if let Err(e) = generate_ptx(contract_binary_folder.to_str().unwrap(), main_contract_name) {
-            error!("Failed to generate PTX for {}: {}", bin_path.display(), e);
-        }
+    let jobs = args
+        .jobs
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+        .min(found_binaries.len())
+        .max(1);
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .wrap_err("Failed to create thread pool")?;
+
+    // Intermediate files are written next to each binary, so contracts in
+    // different folders never share files across threads.
+    let failed_binaries: Vec<&PathBuf> = pool.install(|| {
+        found_binaries
+            .par_iter()
+            .filter(|bin_path| {
+                info!("Found binary file: {}", bin_path.display());
+                let contract_binary_folder = bin_path
+                    .parent()
+                    .expect("Binary file should have a parent directory");
+                let main_contract_name = bin_path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .expect("Binary file should have a valid name");
+                match generate_ptx(
+                    contract_binary_folder,
+                    main_contract_name,
+                    &args.ptx_toolchain,
+                    &rt_bitcode,
+                ) {
+                    Ok(()) => false,
+                    Err(e) => {
+                        error!("Failed to generate PTX for {}: {}", bin_path.display(), e);
+                        true
+                    }
+                }
+            })
+            .collect()
     });
 
+    if failed_binaries.is_empty() {
+        info!(
+            "PTX generation succeeded for all {} binaries.",
+            found_binaries.len()
+        );
+    } else {
+        error!(
+            "PTX generation failed for {} of {} binaries:",
+            failed_binaries.len(),
+            found_binaries.len()
+        );
+        for bin_path in &failed_binaries {
+            error!("  - {}", bin_path.display());
+        }
+        return Err(eyre!(
+            "PTX generation failed for {} of {} binaries: {}",
+            failed_binaries.len(),
+            found_binaries.len(),
+            failed_binaries
+                .iter()
+                .map(|bin_path| bin_path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_list(content: &str) -> Vec<(String, String, Option<String>)> {
+        let dir = tempfile::tempdir().unwrap();
+        let list_file = dir.path().join("B1.list");
+        fs::write(&list_file, content).unwrap();
+        read_csv_list(&list_file)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.file, entry.contract, entry.version))
+            .collect()
+    }
+
+    #[test]
+    fn read_csv_list_reads_crlf_lines() {
+        assert_eq!(
+            read_list("# file,contract\r\nA,C,0.8.20\r\nB,D\r\n"),
+            vec![
+                ("A".into(), "C".into(), Some("0.8.20".into())),
+                ("B".into(), "D".into(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_csv_list_skips_bom() {
+        assert_eq!(
+            read_list("\u{feff}A,C\nB,D\n"),
+            vec![
+                ("A".into(), "C".into(), None),
+                ("B".into(), "D".into(), None),
+            ]
+        );
+    }
+}
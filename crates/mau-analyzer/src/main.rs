@@ -10,6 +10,7 @@ use types::{Cli, Commands};
 
 mod compile;
 mod plot;
+mod progress;
 mod run;
 mod types;
 
@@ -45,6 +46,7 @@ fn main() -> Result<()> {
         .expect("Setting default tracing subscriber failed");
 
     let cli = Cli::parse();
+    cli.validate()?;
 
     match cli.command {
         Commands::Run(args) => {
@@ -59,7 +61,7 @@ fn main() -> Result<()> {
             info!("Executing 'compile' command...");
             handle_compile_command(args)?;
         }
-        Commands::PTX(args) => {
+        Commands::Ptx(args) => {
             handle_ptx_command(args)?;
         }
     }
@@ -1,20 +1,36 @@
 use crate::types::{PlotArgs, StatsEntry};
-use csv::Reader; // Added Reader
+use csv::{ReaderBuilder, StringRecord};
 use eyre::{Result, WrapErr, eyre};
 use glob::glob;
 use plotters::prelude::*;
 use std::collections::{BTreeMap, HashMap};
 use std::fs::{self};
+use std::io::Write;
 use std::path::Path;
 use tracing::info;
 // Added Deserialize
 
+/// Reads the stats entries of one contract. CSVs with a `time_taken_millis`
+/// column, as written by ityfuzz-analyzer, are converted to nanoseconds.
 fn read_stats_from_csv(csv_path: &Path) -> Result<Vec<StatsEntry>> {
-    let mut rdr = Reader::from_path(csv_path)
+    let mut rdr = ReaderBuilder::new()
+        .comment(Some(b'#'))
+        .from_path(csv_path)
         .wrap_err_with(|| format!("Failed to open CSV file: {}", csv_path.display()))?;
+    let headers = rdr
+        .headers()
+        .wrap_err_with(|| format!("Failed to read CSV header of {}", csv_path.display()))?
+        .clone();
+    let (headers, millis_column) =
+        rename_time_column(headers, "time_taken_millis", "time_taken_nanos");
     let mut entries = Vec::new();
-    for result in rdr.deserialize() {
-        let entry: StatsEntry = result.wrap_err_with(|| {
+    for result in rdr.records() {
+        let mut record = result
+            .wrap_err_with(|| format!("Failed to read record from {}", csv_path.display()))?;
+        if let Some(column) = millis_column {
+            record = scale_time_field(&record, column, |millis| millis.saturating_mul(1_000_000));
+        }
+        let entry: StatsEntry = record.deserialize(Some(&headers)).wrap_err_with(|| {
             format!("Failed to deserialize record from {}", csv_path.display())
         })?;
         entries.push(entry);
@@ -22,6 +38,43 @@ fn read_stats_from_csv(csv_path: &Path) -> Result<Vec<StatsEntry>> {
     Ok(entries)
 }
 
+/// Renames the `from` time column of `headers` to `to` unless `to` is
+/// present already, returning the column's index if it was renamed
+fn rename_time_column(
+    headers: StringRecord,
+    from: &str,
+    to: &str,
+) -> (StringRecord, Option<usize>) {
+    if headers.iter().any(|header| header == to) {
+        return (headers, None);
+    }
+    let Some(column) = headers.iter().position(|header| header == from) else {
+        return (headers, None);
+    };
+    let renamed = headers
+        .iter()
+        .map(|header| if header == from { to } else { header })
+        .collect();
+    (renamed, Some(column))
+}
+
+/// Applies `scale` to the time value in `column` of `record`. Values that
+/// are not numbers are kept, so deserializing reports them.
+fn scale_time_field(
+    record: &StringRecord,
+    column: usize,
+    scale: impl Fn(u64) -> u64,
+) -> StringRecord {
+    record
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match field.parse::<u64>() {
+            Ok(value) if i == column => scale(value).to_string(),
+            _ => field.to_string(),
+        })
+        .collect()
+}
+
 pub fn aggregate_and_plot_data(
     all_contract_stats: &HashMap<String, Vec<StatsEntry>>,
     plot_output_dir: &Path,
@@ -84,8 +137,18 @@ pub fn aggregate_and_plot_data(
     }
 
     // store the overall csv stats
-    store_overall_stats_csv(plot_output_dir, &title_prefix,  &plot_data)?;
-    store_simplified_stats_csv(plot_output_dir, &title_prefix, &plot_data)?;
+    store_overall_stats_csv(
+        plot_output_dir,
+        &title_prefix,
+        all_contract_stats.len(),
+        &plot_data,
+    )?;
+    store_simplified_stats_csv(
+        plot_output_dir,
+        &title_prefix,
+        all_contract_stats.len(),
+        &plot_data,
+    )?;
 
     let plot_path = plot_output_dir.join(format!("{}_overall_instructions_plot.png", title_prefix));
 
@@ -127,11 +190,27 @@ pub fn aggregate_and_plot_data(
         .wrap_err("Failed to draw data series on chart")?;
 
     root_area.present().wrap_err("Failed to present chart")?;
+    check_plot_written(&plot_path)?;
     info!("Plot saved to {}", plot_path.display());
 
     Ok(())
 }
 
+/// Fails if `plot_path` is missing or empty after `present()`. Plotters can
+/// write a 0-byte PNG without an error when no font could be loaded.
+fn check_plot_written(plot_path: &Path) -> Result<()> {
+    let len = fs::metadata(plot_path).map_or(0, |m| m.len());
+    if len == 0 {
+        return Err(eyre!(
+            "Plot {} was not written or is empty; this usually means no font could be loaded. \
+             Install a system font (e.g. fonts-dejavu-core and fontconfig) or build plotters \
+             with the `ab_glyph` feature",
+            plot_path.display()
+        ));
+    }
+    Ok(())
+}
+
 pub fn handle_plot_command(args: PlotArgs) -> Result<()> {
     if !args.output_dir.exists() {
         return Err(eyre!(
@@ -249,15 +328,35 @@ pub fn handle_plot_command(args: PlotArgs) -> Result<()> {
     Ok(())
 }
 
-fn store_overall_stats_csv(plot_output_dir: &Path, title_prefix: &str,  plot_data: &[(f64, f64)]) -> Result<()> {
+/// Creates an overall stats CSV, starting with `#` comment lines that describe
+/// where the numbers come from. Readers skip them with `ReaderBuilder::comment`.
+fn create_overall_csv(
+    csv_path: &Path,
+    title_prefix: &str,
+    num_contracts: usize,
+) -> Result<csv::Writer<fs::File>> {
+    let mut file = fs::File::create(csv_path)
+        .wrap_err_with(|| format!("Failed to create CSV writer for {}", csv_path.display()))?;
+    writeln!(
+        file,
+        "# title: {}\n# generated_at: {}\n# contracts: {}\n# time_unit: seconds",
+        title_prefix,
+        chrono::Utc::now().to_rfc3339(),
+        num_contracts
+    )
+    .wrap_err_with(|| format!("Failed to write metadata to {}", csv_path.display()))?;
+    Ok(csv::Writer::from_writer(file))
+}
+
+fn store_overall_stats_csv(
+    plot_output_dir: &Path,
+    title_prefix: &str,
+    num_contracts: usize,
+    plot_data: &[(f64, f64)],
+) -> Result<()> {
     let overall_stats_csv_path =
         plot_output_dir.join(format!("{}_overall_instructions_stats.csv", title_prefix));
-    let mut wtr = csv::Writer::from_path(&overall_stats_csv_path).wrap_err_with(|| {
-        format!(
-            "Failed to create CSV writer for {}",
-            overall_stats_csv_path.display()
-        )
-    })?;
+    let mut wtr = create_overall_csv(&overall_stats_csv_path, title_prefix, num_contracts)?;
     wtr.write_record(["time_seconds", "instructions(k)"])
         .wrap_err("Failed to write CSV header")?;
 
@@ -270,23 +369,31 @@ fn store_overall_stats_csv(plot_output_dir: &Path, title_prefix: &str,  plot_dat
     Ok(())
 }
 
-
-fn store_simplified_stats_csv(plot_output_dir: &Path, title_prefix: &str,  plot_data: &[(f64, f64)]) -> Result<()> {
-    let overall_stats_csv_path =
-        plot_output_dir.join(format!("{}_overall_instructions_stats_simplified.csv", title_prefix));
-    let mut wtr = csv::Writer::from_path(&overall_stats_csv_path).wrap_err_with(|| {
-        format!(
-            "Failed to create CSV writer for {}",
-            overall_stats_csv_path.display()
-        )
-    })?;
-
-    let time_steps= [1.0, 5.0, 10.0, 30.0];
-    let data:&Vec<(f64, f64)>  = &time_steps.iter().map(|t|{
-        let instr = plot_data.iter().filter(|(x, _)| *x<=*t).next_back().unwrap().1;
-        (*t, instr)
-    }).collect();
-
+fn store_simplified_stats_csv(
+    plot_output_dir: &Path,
+    title_prefix: &str,
+    num_contracts: usize,
+    plot_data: &[(f64, f64)],
+) -> Result<()> {
+    let overall_stats_csv_path = plot_output_dir.join(format!(
+        "{}_overall_instructions_stats_simplified.csv",
+        title_prefix
+    ));
+    let mut wtr = create_overall_csv(&overall_stats_csv_path, title_prefix, num_contracts)?;
+
+    let time_steps = [1.0, 5.0, 10.0, 30.0];
+    let data: &Vec<(f64, f64)> = &time_steps
+        .iter()
+        .map(|t| {
+            let instr = plot_data
+                .iter()
+                .filter(|(x, _)| *x <= *t)
+                .next_back()
+                .unwrap()
+                .1;
+            (*t, instr)
+        })
+        .collect();
 
     wtr.write_record(["time_seconds", "instructions(k)"])
         .wrap_err("Failed to write CSV header")?;
@@ -0,0 +1,65 @@
+use crate::types::ProgressMode;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::borrow::Cow;
+use std::io::IsTerminal;
+
+/// Progress reporting that draws an animated bar on a terminal and falls back
+/// to plain text lines on stderr when the output is a log file, e.g. in CI.
+#[derive(Clone)]
+pub struct Progress {
+    bar: ProgressBar,
+    plain: bool,
+}
+
+impl Progress {
+    pub fn new(len: u64, mode: ProgressMode) -> Self {
+        let mode = match mode {
+            ProgressMode::Auto if std::io::stderr().is_terminal() => ProgressMode::Bar,
+            ProgressMode::Auto => ProgressMode::Plain,
+            mode => mode,
+        };
+
+        let bar = ProgressBar::new(len);
+        if mode == ProgressMode::Bar {
+            bar.set_style(
+                ProgressStyle::with_template(
+                    "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})\n{msg}",
+                )
+                .unwrap()
+                .progress_chars("█▓▒░ "),
+            );
+        } else {
+            bar.set_draw_target(ProgressDrawTarget::hidden());
+        }
+
+        Self {
+            bar,
+            plain: mode == ProgressMode::Plain,
+        }
+    }
+
+    pub fn inc(&self, delta: u64) {
+        self.bar.inc(delta);
+    }
+
+    pub fn set_message(&self, msg: impl Into<Cow<'static, str>>) {
+        let msg = msg.into();
+        if self.plain {
+            eprintln!(
+                "[{}/{}] {}",
+                self.bar.position(),
+                self.bar.length().unwrap_or_default(),
+                msg
+            );
+        }
+        self.bar.set_message(msg);
+    }
+
+    pub fn finish_with_message(&self, msg: impl Into<Cow<'static, str>>) {
+        let msg = msg.into();
+        if self.plain {
+            eprintln!("{}", msg);
+        }
+        self.bar.finish_with_message(msg);
+    }
+}
@@ -1,12 +1,13 @@
+use crate::compile::{CHECKSUMS_FILE, read_checksums, read_csv_list, sha256_file};
 use crate::plot::aggregate_and_plot_data;
+use crate::progress::Progress;
 use crate::types::RunArgs;
 use crate::types::StatsEntry;
 use csv::Writer;
 use eyre::{Result, WrapErr, eyre};
 use glob::glob;
-use indicatif::{ProgressBar, ProgressStyle};
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{self};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
@@ -14,16 +15,8 @@ use tracing::error;
 use tracing::info;
 
 pub fn handle_run_command(args: RunArgs) -> Result<()> {
-    fs::create_dir_all(&args.output_dir).wrap_err_with(|| {
-        format!(
-            "Failed to create output directory: {}",
-            args.output_dir.display()
-        )
-    })?;
-
-    let mut all_contract_stats: HashMap<String, Vec<StatsEntry>> = HashMap::new();
-
-    let benchmark_glob_pattern = format!("{}/*", args.benchmark_base_dir.to_string_lossy());
+    let benchmark_base_dir = normalize_dir(&args.benchmark_base_dir);
+    let benchmark_glob_pattern = format!("{}/*", benchmark_base_dir.to_string_lossy());
 
     let glob_pattern_results = glob(&benchmark_glob_pattern)
         .wrap_err_with(|| format!("Invalid glob pattern: '{}'", benchmark_glob_pattern))?;
@@ -46,14 +39,46 @@ pub fn handle_run_command(args: RunArgs) -> Result<()> {
 
     info!("Found {} contract directories", contract_dirs.len());
 
-    let pb = ProgressBar::new(contract_dirs.len() as u64);
-    pb.set_style(
-        ProgressStyle::with_template(
-            "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})\n{msg}",
+    if let Some(only) = &args.only {
+        let selected: HashSet<String> = read_csv_list(only)?
+            .into_iter()
+            .map(|entry| entry.file)
+            .collect();
+        contract_dirs.retain(|dir| {
+            dir.file_name()
+                .is_some_and(|name| selected.contains(name.to_string_lossy().as_ref()))
+        });
+        info!(
+            "Running {} contract directories listed in {}",
+            contract_dirs.len(),
+            only.display()
+        );
+    }
+
+    if args.list_contracts {
+        for contract_dir_path in &contract_dirs {
+            println!("{}", contract_dir_path.display());
+        }
+        println!("{} contract directories", contract_dirs.len());
+        return Ok(());
+    }
+
+    fs::create_dir_all(&args.output_dir).wrap_err_with(|| {
+        format!(
+            "Failed to create output directory: {}",
+            args.output_dir.display()
         )
-        .unwrap()
-        .progress_chars("█▓▒░ "),
-    );
+    })?;
+
+    let checksums = if args.verify_checksums {
+        read_checksums(&benchmark_base_dir.join(CHECKSUMS_FILE))?
+    } else {
+        BTreeMap::new()
+    };
+
+    let mut all_contract_stats: HashMap<String, Vec<StatsEntry>> = HashMap::new();
+
+    let pb = Progress::new(contract_dirs.len() as u64, args.progress);
     pb.set_message("Starting fuzzing...");
 
     for contract_dir_path in contract_dirs {
@@ -66,6 +91,13 @@ pub fn handle_run_command(args: RunArgs) -> Result<()> {
 
         pb.set_message(format!("Fuzzing contract: {}", contract_id));
 
+        if args.verify_checksums {
+            if let Err(e) = verify_checksums(&benchmark_base_dir, &contract_id, &checksums) {
+                error!("Skipping contract {}: {:?}", contract_id, e);
+                continue;
+            }
+        }
+
         let contract_files_glob = format!("{}/*", contract_dir_path.to_string_lossy());
         let mut options = vec![];
         for option in args.fuzzer_options.iter() {
@@ -165,6 +197,49 @@ pub fn handle_run_command(args: RunArgs) -> Result<()> {
     Ok(())
 }
 
+/// Canonicalizes a user-supplied directory so that inputs like `b1/` or `b1/.`
+/// yield clean paths whose components can be used as contract ids
+fn normalize_dir(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.components().collect())
+}
+
+/// Compares the artifacts of a contract with the checksums recorded by the
+/// compile command, e.g. to catch files left over from an interrupted recompile
+fn verify_checksums(
+    benchmark_base_dir: &Path,
+    contract_id: &str,
+    checksums: &BTreeMap<PathBuf, String>,
+) -> Result<()> {
+    let mut verified = 0;
+    for (name, expected) in checksums
+        .iter()
+        .filter(|(name, _)| name.starts_with(contract_id))
+    {
+        let path = benchmark_base_dir.join(name);
+        let actual = sha256_file(&path)?;
+        if actual != *expected {
+            return Err(eyre!(
+                "Checksum mismatch for {}: expected {}, found {}; recompile the contract",
+                path.display(),
+                expected,
+                actual
+            ));
+        }
+        verified += 1;
+    }
+    if verified == 0 {
+        return Err(eyre!(
+            "No checksums recorded in {} for this contract",
+            CHECKSUMS_FILE
+        ));
+    }
+    info!(
+        "Verified checksums of {} files of {}",
+        verified, contract_id
+    );
+    Ok(())
+}
+
 fn run_program_with_timeout(
     program_path: &str,
     args: &[&str],
@@ -223,7 +298,7 @@ fn parse_log(log_content: &str, contract_id: &str) -> Result<Vec<StatsEntry>> {
 
     let mut began_at_nanos: Option<u64> = None;
 
-    for line in log_content.lines() {
+    for line in log_content.lines().map(normalize_line) {
         if began_at_nanos.is_none() {
             if let Some(caps) = began_at_re.captures(line) {
                 began_at_nanos = Some(caps[1].parse::<u64>().wrap_err_with(|| {
@@ -279,6 +354,12 @@ fn parse_log(log_content: &str, contract_id: &str) -> Result<Vec<StatsEntry>> {
     Ok(entries)
 }
 
+/// Strips a leading UTF-8 BOM and a trailing `\r` left over from CRLF line endings.
+pub fn normalize_line(line: &str) -> &str {
+    let line = line.strip_prefix('\u{feff}').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
 fn write_csv(contract_id: &str, entries: &[StatsEntry], output_path_base: &Path) -> Result<()> {
     let csv_path = output_path_base.join(format!("{}.instructions.stats.csv", contract_id));
     let mut wtr = Writer::from_path(&csv_path)
@@ -290,3 +371,76 @@ fn write_csv(contract_id: &str, entries: &[StatsEntry], output_path_base: &Path)
     wtr.flush().wrap_err("Failed to flush CSV writer")?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "Began at 1000
+Instruction Covered: 5; Branch Covered: 1 Timestamp Nanos: 1500
+Instruction Covered: 9; Branch Covered: 2 Timestamp Nanos: 2000
+";
+
+    fn covered(entries: &[StatsEntry]) -> Vec<(u64, u64, u64)> {
+        entries
+            .iter()
+            .map(|e| {
+                (
+                    e.time_taken_nanos,
+                    e.instructions_covered,
+                    e.branches_covered,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_log_reads_crlf_lines() {
+        let entries = parse_log(&LOG.replace('\n', "\r\n"), "c1").unwrap();
+        assert_eq!(covered(&entries), vec![(500, 5, 1), (1000, 9, 2)]);
+    }
+
+    #[test]
+    fn parse_log_skips_bom() {
+        let entries = parse_log(&format!("\u{feff}{}", LOG), "c1").unwrap();
+        assert_eq!(covered(&entries), vec![(500, 5, 1), (1000, 9, 2)]);
+    }
+
+    #[test]
+    fn normalize_dir_strips_trailing_slash_and_dot() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_dir = dir.path().join("b1");
+        fs::create_dir_all(base_dir.join("c1")).unwrap();
+
+        for input in [
+            format!("{}/", base_dir.display()),
+            format!("{}/.", base_dir.display()),
+        ] {
+            let normalized = normalize_dir(Path::new(&input));
+            assert_eq!(normalized, fs::canonicalize(&base_dir).unwrap());
+            let contract_ids: Vec<String> = glob(&format!("{}/*", normalized.to_string_lossy()))
+                .unwrap()
+                .map(|path| {
+                    path.unwrap()
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect();
+            assert_eq!(contract_ids, vec!["c1"]);
+        }
+    }
+
+    #[test]
+    fn normalize_dir_keeps_missing_dir_name() {
+        assert_eq!(
+            normalize_dir(Path::new("missing/b1/.")),
+            PathBuf::from("missing/b1")
+        );
+        assert_eq!(
+            normalize_dir(Path::new("missing/b1/")),
+            PathBuf::from("missing/b1")
+        );
+    }
+}
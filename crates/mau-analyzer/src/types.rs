@@ -1,5 +1,7 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use eyre::eyre;
 use serde::{Deserialize, Serialize};
+use std::fs;
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
@@ -12,13 +14,17 @@ pub struct CompileArgs {
     )]
     pub list_file: PathBuf,
 
+    /// Format of the list file
+    #[arg(long, value_enum, default_value_t = ListFormat::Csv)]
+    pub list_format: ListFormat,
+
     /// Base directory containing .sol files to compile (e.g., release/benchmarks/B1/sol)
     #[arg(long, value_name = "DIR")]
     pub solc_input_dir: PathBuf,
 
     /// Base output directory for compiled contracts (e.g., b1 or output_b1)
     /// Each contract will get a subdirectory here: <solc_output_dir>/<contract_filename_base>/
-    #[arg(long, value_name = "DIR" )]
+    #[arg(long, value_name = "DIR")]
     pub solc_output_dir: PathBuf,
 
     /// Timeout in seconds for solc compilation per contract
@@ -32,15 +38,128 @@ pub struct CompileArgs {
     /// Generate PTX files for GPU execution (requires ptxsema, llvm tools)
     #[arg(long)]
     pub generate_ptx: bool,
+
+    /// Keep the intermediate LLVM files (bytecode.ll, kernel.bc, kernel.ll)
+    /// produced by PTX generation instead of removing them during cleanup
+    #[arg(long)]
+    pub keep_ptx_intermediates: bool,
+
+    /// Also keep files with this extension (e.g. `json`) or exact file name
+    /// (e.g. `combined.json`) during cleanup (repeatable)
+    #[arg(long, value_name = "EXT|FILE")]
+    pub keep_ext: Vec<String>,
+
+    #[command(flatten)]
+    pub ptx_toolchain: PtxToolchainArgs,
+
+    /// Run `solc-select install <version>` when the per-contract compiler
+    /// version from the list file is not installed yet
+    #[arg(long)]
+    pub auto_install_solc: bool,
+
+    /// Pass `--optimize` to solc
+    #[arg(long)]
+    pub optimize: bool,
+
+    /// Pass `--optimize-runs <RUNS>` to solc
+    #[arg(long, value_name = "RUNS")]
+    pub optimize_runs: Option<u32>,
+
+    /// Pass `--evm-version <VERSION>` to solc (e.g., byzantium, london)
+    #[arg(long, value_name = "VERSION")]
+    pub evm_version: Option<String>,
+
+    /// How to report progress: an animated bar, plain text lines for CI logs, or nothing
+    #[arg(long, value_enum, default_value_t = ProgressMode::Auto)]
+    pub progress: ProgressMode,
+}
+
+impl CompileArgs {
+    pub fn validate(&self) -> eyre::Result<()> {
+        if self.keep_ptx_intermediates && !self.generate_ptx {
+            return Err(eyre!(
+                "--keep-ptx-intermediates only applies together with --generate-ptx"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Format of the benchmark list file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ListFormat {
+    /// One `<file>,<contract>[,<version>]` entry per line, `#` starts a comment
+    Csv,
+    /// A JSON array of `{file, contract, version, optimize}` objects
+    Json,
+}
+
+/// A contract to compile, read from the benchmark list file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListEntry {
+    /// Solidity file name without the `.sol` extension
+    pub file: String,
+    /// Name of the main contract in the file
+    pub contract: String,
+    /// solc version installed by solc-select
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Overrides `--optimize` for this contract
+    #[serde(default)]
+    pub optimize: Option<bool>,
 }
 
 #[derive(Parser, Debug)]
-pub struct PTXArgs{
+pub struct PTXArgs {
     /// Output directory of `compile` command. Folder structure should be:
     /// <solc_output_dir>/<contract_folder>/<main_contract_name>.bin
     #[arg(long, value_name = "DIR")]
     pub solc_output_dir: PathBuf,
 
+    /// Number of contracts to generate PTX files for in parallel; defaults to
+    /// the number of CPUs
+    #[arg(short, long, value_name = "NUM", value_parser = parse_jobs)]
+    pub jobs: Option<usize>,
+
+    #[command(flatten)]
+    pub ptx_toolchain: PtxToolchainArgs,
+}
+
+fn parse_jobs(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("must be at least 1; omit --jobs to use one job per CPU".to_string()),
+        Ok(jobs) => Ok(jobs),
+        Err(_) => Err(format!("expected a positive number, got '{}'", s)),
+    }
+}
+
+/// Tools and settings used by the PTX generation pipeline
+#[derive(Args, Debug, Clone)]
+pub struct PtxToolchainArgs {
+    /// ptxsema binary used to lift EVM bytecode to LLVM IR
+    #[arg(long, value_name = "PATH", default_value = "ptxsema")]
+    pub ptxsema: String,
+
+    /// llvm-link binary
+    #[arg(long, value_name = "PATH", default_value = "llvm-link")]
+    pub llvm_link: String,
+
+    /// llvm-dis binary
+    #[arg(long, value_name = "PATH", default_value = "llvm-dis")]
+    pub llvm_dis: String,
+
+    /// llc binary used to emit PTX
+    #[arg(long, value_name = "PATH", default_value = "llc-16")]
+    pub llc: String,
+
+    /// Runtime bitcode linked into each kernel; a relative path is resolved
+    /// against the current directory
+    #[arg(long, value_name = "PATH", default_value = "rt.o.bc")]
+    pub rt_bitcode: PathBuf,
+
+    /// Target GPU architecture passed to llc as `-mcpu`
+    #[arg(long, value_name = "CPU", default_value = "sm_86")]
+    pub mcpu: String,
 }
 
 #[derive(Parser, Debug)]
@@ -59,7 +178,18 @@ pub enum Commands {
     /// Compile contracts from source code and optionally generate PTX files
     Compile(CompileArgs),
     /// Generate PTX files for compiled contracts
-    PTX(PTXArgs),
+    Ptx(PTXArgs),
+}
+
+impl Cli {
+    /// Rejects flag combinations clap cannot express, before any work starts
+    pub fn validate(&self) -> eyre::Result<()> {
+        match &self.command {
+            Commands::Run(args) => args.validate(),
+            Commands::Compile(args) => args.validate(),
+            Commands::Plot(_) | Commands::Ptx(_) => Ok(()),
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -87,6 +217,54 @@ pub struct RunArgs {
     /// Whether to use PTX files for GPU execution (requires pre-compiled kernel.ptx files in the output directory)
     #[arg(long)]
     pub use_ptx: bool,
+
+    /// How to report progress: an animated bar, plain text lines for CI logs, or nothing
+    #[arg(long, value_enum, default_value_t = ProgressMode::Auto)]
+    pub progress: ProgressMode,
+
+    /// Only run the contracts listed in this file, e.g. the `compiled.list`
+    /// written by the compile command
+    #[arg(long, value_name = "FILE")]
+    pub only: Option<PathBuf>,
+
+    /// Print the contract directories that would be fuzzed, in processing
+    /// order, and exit without running the fuzzer
+    #[arg(long)]
+    pub list_contracts: bool,
+
+    /// Check each contract's files against the `checksums.txt` written by the
+    /// compile command and skip contracts whose artifacts changed since
+    #[arg(long)]
+    pub verify_checksums: bool,
+}
+
+impl RunArgs {
+    pub fn validate(&self) -> eyre::Result<()> {
+        if self.use_ptx {
+            // Any contract directory with a kernel.ptx shows PTX generation ran
+            let has_ptx = fs::read_dir(&self.benchmark_base_dir).is_ok_and(|entries| {
+                entries
+                    .flatten()
+                    .any(|entry| entry.path().join("kernel.ptx").is_file())
+            });
+            if !has_ptx {
+                return Err(eyre!(
+                    "--use-ptx needs kernel.ptx files, but no contract directory in {} has one; generate them with `compile --generate-ptx` or `ptx`",
+                    self.benchmark_base_dir.display()
+                ));
+            }
+        }
+        if self.verify_checksums {
+            let checksums_path = self.benchmark_base_dir.join(crate::compile::CHECKSUMS_FILE);
+            if !checksums_path.is_file() {
+                return Err(eyre!(
+                    "--verify-checksums needs {}, which the compile command writes",
+                    checksums_path.display()
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -96,6 +274,19 @@ pub struct PlotArgs {
     pub output_dir: PathBuf,
 }
 
+/// How progress is reported on the console
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProgressMode {
+    /// Animated bar on a terminal, plain lines otherwise
+    Auto,
+    /// Always draw the animated progress bar
+    Bar,
+    /// Print plain text progress lines, suitable for CI logs
+    Plain,
+    /// Do not report progress
+    None,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StatsEntry {
     pub instructions_covered: u64,